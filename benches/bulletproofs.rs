@@ -7,17 +7,21 @@ extern crate rand;
 use rand::Rng;
 
 extern crate curve25519_dalek;
+use curve25519_dalek::ristretto::RistrettoPoint;
 use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::VartimeMultiscalarMul;
 
 extern crate merlin;
 use merlin::Transcript;
 
 extern crate bulletproofs;
-use bulletproofs::RangeProof;
 use bulletproofs::{BulletproofGens, PedersenGens};
+use bulletproofs::{InnerProductProof, RangeProof};
 
 static AGGREGATION_SIZES: [usize; 6] = [1, 2, 4, 8, 16, 32];
 
+static IPP_BATCH_SIZES: [usize; 3] = [1, 4, 16];
+
 fn create_aggregated_rangeproof_helper(n: usize, c: &mut Criterion) {
     let label = format!("Aggregated {}-bit rangeproof creation", n);
 
@@ -29,7 +33,7 @@ fn create_aggregated_rangeproof_helper(n: usize, c: &mut Criterion) {
             let mut rng = rand::thread_rng();
 
             let (min, max) = (0u64, ((1u128 << n) - 1) as u64);
-            let values: Vec<u64> = (0..m).map(|_| rng.gen_range(min, max)).collect();
+            let values: Vec<u128> = (0..m).map(|_| rng.gen_range(min, max) as u128).collect();
             let blindings: Vec<Scalar> = (0..m).map(|_| Scalar::random(&mut rng)).collect();
 
             b.iter(|| {
@@ -77,7 +81,7 @@ fn verify_aggregated_rangeproof_helper(n: usize, c: &mut Criterion) {
             let mut rng = rand::thread_rng();
 
             let (min, max) = (0u64, ((1u128 << n) - 1) as u64);
-            let values: Vec<u64> = (0..m).map(|_| rng.gen_range(min, max)).collect();
+            let values: Vec<u128> = (0..m).map(|_| rng.gen_range(min, max) as u128).collect();
             let blindings: Vec<Scalar> = (0..m).map(|_| Scalar::random(&mut rng)).collect();
 
             let mut transcript = Transcript::new(b"AggregateRangeProofBenchmark");
@@ -117,6 +121,104 @@ fn verify_aggregated_rangeproof_n_64(c: &mut Criterion) {
     verify_aggregated_rangeproof_helper(64, c);
 }
 
+/// Creates `batch_size` inner-product proofs of length `n` over the
+/// same bases, returning the proofs, their points `P`, and the bases.
+fn ipp_batch(
+    n: usize,
+    batch_size: usize,
+) -> (
+    Vec<InnerProductProof>,
+    Vec<RistrettoPoint>,
+    RistrettoPoint,
+    Vec<RistrettoPoint>,
+    Vec<RistrettoPoint>,
+) {
+    let bp_gens = BulletproofGens::new(n, 1);
+    let G: Vec<RistrettoPoint> = bp_gens.share(0).G(n).cloned().collect();
+    let H: Vec<RistrettoPoint> = bp_gens.share(0).H(n).cloned().collect();
+    let mut rng = rand::thread_rng();
+    let Q = RistrettoPoint::random(&mut rng);
+    let ones = vec![Scalar::one(); n];
+
+    let mut proofs = Vec::with_capacity(batch_size);
+    let mut Ps = Vec::with_capacity(batch_size);
+    for _ in 0..batch_size {
+        let a: Vec<Scalar> = (0..n).map(|_| Scalar::random(&mut rng)).collect();
+        let b: Vec<Scalar> = (0..n).map(|_| Scalar::random(&mut rng)).collect();
+        let c = a
+            .iter()
+            .zip(b.iter())
+            .fold(Scalar::zero(), |acc, (a_i, b_i)| acc + a_i * b_i);
+
+        Ps.push(RistrettoPoint::vartime_multiscalar_mul(
+            a.iter().chain(b.iter()).chain(Some(&c)),
+            G.iter().chain(H.iter()).chain(Some(&Q)),
+        ));
+
+        let mut transcript = Transcript::new(b"IPPBatchBenchmark");
+        proofs.push(InnerProductProof::create(
+            &mut transcript,
+            &Q,
+            &ones,
+            G.clone(),
+            H.clone(),
+            a,
+            b,
+        ));
+    }
+
+    (proofs, Ps, Q, G, H)
+}
+
+fn verify_ipp_single(c: &mut Criterion) {
+    let n = 64;
+
+    c.bench_function_over_inputs(
+        "Inner-product proof verification, one at a time",
+        move |b, &&batch_size| {
+            let (proofs, Ps, Q, G, H) = ipp_batch(n, batch_size);
+            let ones = vec![Scalar::one(); n];
+
+            b.iter(|| {
+                for (proof, P) in proofs.iter().zip(Ps.iter()) {
+                    let mut transcript = Transcript::new(b"IPPBatchBenchmark");
+                    proof
+                        .verify(&mut transcript, &ones, P, &Q, &G, &H)
+                        .unwrap();
+                }
+            })
+        },
+        &IPP_BATCH_SIZES,
+    );
+}
+
+fn verify_ipp_batch(c: &mut Criterion) {
+    let n = 64;
+
+    c.bench_function_over_inputs(
+        "Inner-product proof verification, batched",
+        move |b, &&batch_size| {
+            let (proofs, Ps, Q, G, H) = ipp_batch(n, batch_size);
+            let ones = vec![Scalar::one(); n];
+            let factors = vec![ones.as_slice(); batch_size];
+
+            b.iter(|| {
+                let mut transcripts = vec![Transcript::new(b"IPPBatchBenchmark"); batch_size];
+                InnerProductProof::verify_batch(
+                    &proofs,
+                    &mut transcripts,
+                    &factors,
+                    &Ps,
+                    &Q,
+                    &G,
+                    &H,
+                ).unwrap();
+            })
+        },
+        &IPP_BATCH_SIZES,
+    );
+}
+
 criterion_group!{
     name = create_rp;
     config = Criterion::default().sample_size(10);
@@ -137,4 +239,12 @@ criterion_group!{
     verify_aggregated_rangeproof_n_64,
 }
 
-criterion_main!(create_rp, verify_rp);
+criterion_group!{
+    name = verify_ipp;
+    config = Criterion::default();
+    targets =
+    verify_ipp_single,
+    verify_ipp_batch,
+}
+
+criterion_main!(create_rp, verify_rp, verify_ipp);
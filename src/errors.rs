@@ -1,45 +1,201 @@
 //! Errors related to proving and verifying proofs.
 
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use core::fmt;
+#[cfg(feature = "std")]
+use std::error;
+
 /// Represents an error in proof creation, verification, or parsing.
-#[derive(Fail, Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub enum ProofError {
-    /// This error occurs when a proof failed to verify.
-    #[fail(display = "Proof verification failed.")]
+    /// This error occurs when a well-formed proof failed the final
+    /// verification check, that is, when the proof is not valid for
+    /// the statement.
     VerificationError,
-    /// This error occurs when the proof encoding is malformed.
-    #[fail(display = "Proof data could not be parsed.")]
+    /// This error occurs when the proof encoding is malformed, for
+    /// instance when it contains a non-canonical scalar.
     FormatError,
+    /// This error occurs when parsing a proof whose encoding is not
+    /// a whole number of 32-byte elements, or not the number of
+    /// elements a proof with a whole number of rounds has.
+    WrongProofLength {
+        /// The length of the shortest valid encoding at least as long
+        /// as the one supplied.
+        expected: usize,
+        /// The length of the encoding supplied.
+        actual: usize,
+    },
+    /// This error occurs when the inner-product proof has the wrong
+    /// number of rounds, \\(\lceil \lg n \rceil\\) for vectors of
+    /// length \\(n\\), for the statement, or more rounds than an
+    /// encoding may have.
+    WrongNumRounds {
+        /// The number of rounds for the statement, or, when parsing,
+        /// the largest number of rounds an encoding may have.
+        expected: usize,
+        /// The number of rounds in the proof.
+        actual: usize,
+    },
+    /// This error occurs during verification when a point in the
+    /// proof or a commitment is not a valid compressed Ristretto point.
+    PointDecompressionError {
+        /// The name of the point which failed to decompress.
+        which: &'static str,
+    },
     /// This error occurs during proving if the number of blinding
     /// factors does not match the number of values.
-    #[fail(display = "Wrong number of blinding factors supplied.")]
-    WrongNumBlindingFactors,
+    WrongNumBlindingFactors {
+        /// The number of values.
+        expected: usize,
+        /// The number of blinding factors supplied.
+        actual: usize,
+    },
+    /// This error occurs during verification if the number of
+    /// bitsizes does not match the number of value commitments.
+    WrongNumBitsizes {
+        /// The number of value commitments.
+        expected: usize,
+        /// The number of bitsizes supplied.
+        actual: usize,
+    },
     /// This error occurs when attempting to create a proof with
-    /// bitsize other than \\(8\\), \\(16\\), \\(32\\), or \\(64\\).
-    #[fail(display = "Invalid bitsize, must have n = 8,16,32,64.")]
-    InvalidBitsize,
-    /// This error occurs when attempting to create an aggregated
-    /// proof with non-power-of-two aggregation size.
-    #[fail(display = "Invalid aggregation size, m must be a power of 2.")]
-    InvalidAggregation,
+    /// bitsize other than \\(8\\), \\(16\\), \\(32\\), \\(64\\), or \\(128\\).
+    InvalidBitsize {
+        /// The bitsize which was requested.
+        got: usize,
+        /// The supported bitsizes.
+        allowed: &'static [usize],
+    },
+    /// This error occurs when attempting to create or verify an
+    /// aggregated proof with an invalid number of parties, for
+    /// instance a number which is not a power of two.
+    InvalidAggregation {
+        /// The aggregation size which was requested.
+        got: usize,
+        /// Why the aggregation size is invalid.
+        reason: &'static str,
+    },
     /// This error occurs when the generators are of the wrong length.
-    #[fail(display = "Invalid generators length, must be equal to n.")]
-    InvalidGeneratorsLength,
+    InvalidGeneratorsLength {
+        /// The number of generators needed.
+        expected: usize,
+        /// The number of generators supplied.
+        actual: usize,
+    },
+    /// This error occurs when proving or verifying that a value lies
+    /// in an interval \\([min, max]\\) with \\(min > max\\), or
+    /// when proving for a value outside of the interval.
+    InvalidInterval,
+    /// This error occurs when the `BulletproofGens` have fewer
+    /// generators per party than the bitsize requires.
+    InsufficientGeneratorCapacity {
+        /// The number of generators needed per party.
+        needed: usize,
+        /// The generator capacity of the `BulletproofGens`.
+        available: usize,
+    },
+    /// This error occurs when the `BulletproofGens` have generators
+    /// for fewer parties than the aggregation requires.
+    InsufficientPartyCapacity {
+        /// The number of parties needed.
+        needed: usize,
+        /// The party capacity of the `BulletproofGens`.
+        available: usize,
+    },
     /// This error results from an internal error during proving.
     ///
     /// The single-party prover is implemented by performing
     /// multiparty computation with ourselves.  However, because the
     /// MPC protocol is not exposed by the single-party API, we
     /// consider its errors to be internal errors.
-    #[fail(display = "Internal error during proof creation: {}", _0)]
     ProvingError(MPCError),
 }
 
+impl fmt::Display for ProofError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ProofError::VerificationError => write!(f, "Proof verification failed."),
+            ProofError::FormatError => write!(f, "Proof data could not be parsed."),
+            ProofError::WrongProofLength { expected, actual } => write!(
+                f,
+                "Wrong proof length: expected {} bytes, got {}.",
+                expected,
+                actual
+            ),
+            ProofError::WrongNumRounds { expected, actual } => write!(
+                f,
+                "Wrong number of inner-product rounds: expected {}, got {}.",
+                expected,
+                actual
+            ),
+            ProofError::PointDecompressionError { which } => {
+                write!(f, "Point {} could not be decompressed.", which)
+            }
+            ProofError::WrongNumBlindingFactors { expected, actual } => write!(
+                f,
+                "Wrong number of blinding factors supplied: expected {}, got {}.",
+                expected,
+                actual
+            ),
+            ProofError::WrongNumBitsizes { expected, actual } => write!(
+                f,
+                "Wrong number of bitsizes supplied: expected {}, got {}.",
+                expected,
+                actual
+            ),
+            ProofError::InvalidBitsize { got, allowed } => {
+                write!(f, "Invalid bitsize {}, must be one of {:?}.", got, allowed)
+            }
+            ProofError::InvalidAggregation { got, reason } => {
+                write!(f, "Invalid aggregation size {}: {}.", got, reason)
+            }
+            ProofError::InvalidGeneratorsLength { expected, actual } => write!(
+                f,
+                "Invalid generators length: expected {}, got {}.",
+                expected,
+                actual
+            ),
+            ProofError::InvalidInterval => {
+                write!(f, "Invalid interval, must have min <= value <= max.")
+            }
+            ProofError::InsufficientGeneratorCapacity { needed, available } => write!(
+                f,
+                "Insufficient generator capacity: needed {}, available {}.",
+                needed,
+                available
+            ),
+            ProofError::InsufficientPartyCapacity { needed, available } => write!(
+                f,
+                "Insufficient party capacity: needed {}, available {}.",
+                needed,
+                available
+            ),
+            ProofError::ProvingError(e) => write!(f, "Internal error during proof creation: {}", e),
+        }
+    }
+}
+
 impl From<MPCError> for ProofError {
     fn from(e: MPCError) -> ProofError {
         match e {
-            MPCError::InvalidBitsize => ProofError::InvalidBitsize,
-            MPCError::InvalidAggregation => ProofError::InvalidAggregation,
-            MPCError::InvalidGeneratorsLength => ProofError::InvalidGeneratorsLength,
+            MPCError::InvalidBitsize { got, allowed } => ProofError::InvalidBitsize { got, allowed },
+            MPCError::InvalidAggregation { got, reason } => {
+                ProofError::InvalidAggregation { got, reason }
+            }
+            MPCError::InvalidGeneratorsLength { expected, actual } => {
+                ProofError::InvalidGeneratorsLength { expected, actual }
+            }
+            MPCError::InsufficientGeneratorCapacity { needed, available } => {
+                ProofError::InsufficientGeneratorCapacity { needed, available }
+            }
+            MPCError::InsufficientPartyCapacity { needed, available } => {
+                ProofError::InsufficientPartyCapacity { needed, available }
+            }
+            MPCError::WrongNumBlindingFactors { expected, actual } => {
+                ProofError::WrongNumBlindingFactors { expected, actual }
+            }
             _ => ProofError::ProvingError(e),
         }
     }
@@ -52,43 +208,243 @@ impl From<MPCError> for ProofError {
 /// API: although the MPC protocol is used internally for single-party
 /// proving, its API should not expose the complexity of the MPC
 /// protocol.
-#[derive(Fail, Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub enum MPCError {
     /// This error occurs when the dealer gives a zero challenge,
     /// which would annihilate the blinding factors.
-    #[fail(display = "Dealer gave a malicious challenge value.")]
     MaliciousDealer,
     /// This error occurs when attempting to create a proof with
-    /// bitsize other than \\(8\\), \\(16\\), \\(32\\), or \\(64\\).
-    #[fail(display = "Invalid bitsize, must have n = 8,16,32,64")]
-    InvalidBitsize,
+    /// bitsize other than \\(8\\), \\(16\\), \\(32\\), \\(64\\), or \\(128\\).
+    InvalidBitsize {
+        /// The bitsize which was requested.
+        got: usize,
+        /// The supported bitsizes.
+        allowed: &'static [usize],
+    },
     /// This error occurs when attempting to create an aggregated
-    /// proof with non-power-of-two aggregation size.
-    #[fail(display = "Invalid aggregation size, m must be a power of 2")]
-    InvalidAggregation,
+    /// proof with an invalid number of parties, for instance a
+    /// number which is not a power of two.
+    InvalidAggregation {
+        /// The aggregation size which was requested.
+        got: usize,
+        /// Why the aggregation size is invalid.
+        reason: &'static str,
+    },
     /// This error occurs when the generators are of the wrong length.
-    #[fail(display = "Invalid generators length, must be equal to n.")]
-    InvalidGeneratorsLength,
+    InvalidGeneratorsLength {
+        /// The number of generators needed.
+        expected: usize,
+        /// The number of generators supplied.
+        actual: usize,
+    },
+    /// This error occurs when the `BulletproofGens` have fewer
+    /// generators per party than the bitsize requires.
+    InsufficientGeneratorCapacity {
+        /// The number of generators needed per party.
+        needed: usize,
+        /// The generator capacity of the `BulletproofGens`.
+        available: usize,
+    },
+    /// This error occurs when the `BulletproofGens` have generators
+    /// for fewer parties than the aggregation requires.
+    InsufficientPartyCapacity {
+        /// The number of parties needed.
+        needed: usize,
+        /// The party capacity of the `BulletproofGens`.
+        available: usize,
+    },
+    /// This error occurs when aggregating locally if the number of
+    /// blinding factors does not match the number of values.
+    WrongNumBlindingFactors {
+        /// The number of values.
+        expected: usize,
+        /// The number of blinding factors supplied.
+        actual: usize,
+    },
     /// This error occurs when the dealer is given the wrong number of
     /// value commitments.
-    #[fail(display = "Wrong number of value commitments")]
-    WrongNumBitCommitments,
+    WrongNumBitCommitments {
+        /// The number of parties.
+        expected: usize,
+        /// The number of commitments given.
+        actual: usize,
+    },
+    /// This error occurs when a streaming dealer is given a message
+    /// from a party other than the next one, for instance a message
+    /// which arrived out of order or a duplicate.
+    UnexpectedPosition {
+        /// The position of the next party, or the number of parties
+        /// if all messages were absorbed.
+        expected: usize,
+        /// The position the message was sent for.
+        actual: usize,
+    },
     /// This error occurs when the dealer is given the wrong number of
     /// polynomial commitments.
-    #[fail(display = "Wrong number of value commitments")]
-    WrongNumPolyCommitments,
+    WrongNumPolyCommitments {
+        /// The number of parties.
+        expected: usize,
+        /// The number of commitments given.
+        actual: usize,
+    },
     /// This error occurs when the dealer is given the wrong number of
     /// proof shares.
-    #[fail(display = "Wrong number of proof shares")]
-    WrongNumProofShares,
+    WrongNumProofShares {
+        /// The number of parties.
+        expected: usize,
+        /// The number of shares given.
+        actual: usize,
+    },
     /// This error occurs when one or more parties submit malformed
     /// proof shares.
-    #[fail(
-        display = "Malformed proof shares from parties {:?}",
-        bad_shares
-    )]
     MalformedProofShares {
         /// A vector with the indexes of the parties whose shares were malformed.
         bad_shares: Vec<usize>,
     },
+    /// This error occurs when a saved party or dealer state is
+    /// resumed with parameters or a transcript that don't match the
+    /// ones it was saved with.
+    InvalidSavedState,
+    /// This error occurs when the dealer is asked to abort every
+    /// party, so that the aggregation cannot continue.
+    NoPartiesRemain,
+    /// This error occurs when the dealer is given a position which
+    /// is not the position of one of the parties.
+    PositionOutOfRange {
+        /// The position which was given.
+        position: usize,
+        /// The number of parties, not including the dealer's padding.
+        num_parties: usize,
+    },
+    /// This error occurs when the dealer is asked to finish the
+    /// protocol while the parties at the given positions have not
+    /// sent their proof shares.
+    PartyDropout {
+        /// The positions of the parties whose shares are missing.
+        positions: Vec<usize>,
+    },
+    /// This error occurs when the dealer receives a message from the
+    /// party at position `party` whose `field` is not a valid point,
+    /// or is the identity where that is not allowed.
+    InvalidPoint {
+        /// The position of the party which sent the message.
+        party: usize,
+        /// The name of the invalid point in the message.
+        field: &'static str,
+    },
+    /// This error occurs when the dealer checks the MACs on the
+    /// parties' messages, and the messages from the given positions
+    /// are missing a MAC or fail authentication.
+    InvalidMessageMac {
+        /// The positions of the parties whose messages failed authentication.
+        bad_parties: Vec<usize>,
+    },
+}
+
+impl fmt::Display for MPCError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MPCError::MaliciousDealer => write!(f, "Dealer gave a malicious challenge value."),
+            MPCError::InvalidBitsize { got, allowed } => {
+                write!(f, "Invalid bitsize {}, must be one of {:?}.", got, allowed)
+            }
+            MPCError::InvalidAggregation { got, reason } => {
+                write!(f, "Invalid aggregation size {}: {}.", got, reason)
+            }
+            MPCError::InvalidGeneratorsLength { expected, actual } => write!(
+                f,
+                "Invalid generators length: expected {}, got {}.",
+                expected,
+                actual
+            ),
+            MPCError::InsufficientGeneratorCapacity { needed, available } => write!(
+                f,
+                "Insufficient generator capacity: needed {}, available {}.",
+                needed,
+                available
+            ),
+            MPCError::InsufficientPartyCapacity { needed, available } => write!(
+                f,
+                "Insufficient party capacity: needed {}, available {}.",
+                needed,
+                available
+            ),
+            MPCError::WrongNumBlindingFactors { expected, actual } => write!(
+                f,
+                "Wrong number of blinding factors supplied: expected {}, got {}.",
+                expected,
+                actual
+            ),
+            MPCError::WrongNumBitCommitments { expected, actual } => write!(
+                f,
+                "Wrong number of value commitments: expected {}, got {}",
+                expected,
+                actual
+            ),
+            MPCError::UnexpectedPosition { expected, actual } => write!(
+                f,
+                "Expected a message from party {}, got one from party {}.",
+                expected,
+                actual
+            ),
+            MPCError::WrongNumPolyCommitments { expected, actual } => write!(
+                f,
+                "Wrong number of polynomial commitments: expected {}, got {}",
+                expected,
+                actual
+            ),
+            MPCError::WrongNumProofShares { expected, actual } => write!(
+                f,
+                "Wrong number of proof shares: expected {}, got {}",
+                expected,
+                actual
+            ),
+            MPCError::MalformedProofShares { bad_shares } => {
+                write!(f, "Malformed proof shares from parties {:?}", bad_shares)
+            }
+            MPCError::InvalidSavedState => {
+                write!(f, "Saved state does not match the given parameters.")
+            }
+            MPCError::NoPartiesRemain => write!(f, "All parties were aborted."),
+            MPCError::PositionOutOfRange {
+                position,
+                num_parties,
+            } => write!(
+                f,
+                "Position {} is out of range for {} parties.",
+                position,
+                num_parties
+            ),
+            MPCError::PartyDropout { positions } => {
+                write!(f, "Parties {:?} dropped out before sending their proof shares.", positions)
+            }
+            MPCError::InvalidPoint { party, field } => {
+                write!(f, "Party {} sent an invalid point {}.", party, field)
+            }
+            MPCError::InvalidMessageMac { bad_parties } => {
+                write!(f, "Messages from parties {:?} failed authentication.", bad_parties)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl error::Error for ProofError {
+    fn description(&self) -> &str {
+        "Bulletproofs proof error"
+    }
+
+    fn cause(&self) -> Option<&error::Error> {
+        match self {
+            ProofError::ProvingError(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl error::Error for MPCError {
+    fn description(&self) -> &str {
+        "Bulletproofs aggregation protocol error"
+    }
 }
@@ -4,12 +4,28 @@
 #![allow(non_snake_case)]
 #![deny(missing_docs)]
 
-use curve25519_dalek::ristretto::RistrettoPoint;
+#[cfg(not(feature = "std"))]
+use alloc::sync::Arc;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::sync::Arc;
+
+use byteorder::{ByteOrder, LittleEndian};
+use core::iter::FusedIterator;
+use core::slice;
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
 use curve25519_dalek::scalar::Scalar;
 use curve25519_dalek::traits::MultiscalarMul;
 
 use digest::{ExtendableOutput, Input, XofReader};
-use sha3::{Sha3XofReader, Shake256};
+use sha3::Shake256;
+use subtle::{Choice, ConstantTimeEq};
+
+use serde::de::Visitor;
+use serde::{self, Deserialize, Deserializer, Serialize, Serializer};
+
+use errors::ProofError;
 
 /// Represents a pair of base points for Pedersen commitments.
 #[derive(Copy, Clone)]
@@ -22,51 +38,107 @@ pub struct PedersenGens {
 }
 
 impl PedersenGens {
+    /// Creates Pedersen generators derived with the extendable-output
+    /// function `X` instead of SHAKE256.
+    ///
+    /// The generators are derived from the same labels as the
+    /// default ones, so `PedersenGens::new_with_hasher::<Shake256>()`
+    /// is the same as `PedersenGens::default()`.
+    pub fn new_with_hasher<X>() -> Self
+    where
+        X: ExtendableOutput + Input + Default,
+    {
+        PedersenGens {
+            B: GeneratorsChain::<X>::new(b"Bulletproofs.Generators.B")
+                .next()
+                .unwrap(),
+            B_blinding: GeneratorsChain::<X>::new(b"Bulletproofs.Generators.B_blinding")
+                .next()
+                .unwrap(),
+        }
+    }
+
     /// Creates a Pedersen commitment using the value scalar and a blinding factor.
     pub fn commit(&self, value: Scalar, blinding: Scalar) -> RistrettoPoint {
         RistrettoPoint::multiscalar_mul(&[value, blinding], &[self.B, self.B_blinding])
     }
+
+    /// Serializes the generators into a byte array of the compressed
+    /// points \\(B\\) and \\(B_{blinding}\\).
+    pub fn to_bytes(&self) -> [u8; 64] {
+        let mut buf = [0u8; 64];
+        buf[..32].copy_from_slice(self.B.compress().as_bytes());
+        buf[32..].copy_from_slice(self.B_blinding.compress().as_bytes());
+        buf
+    }
+
+    /// Deserializes the generators from a byte slice.
+    ///
+    /// Returns an error if the slice is not 64 bytes long or either
+    /// point is not a valid compressed Ristretto point.
+    pub fn from_bytes(slice: &[u8]) -> Result<PedersenGens, ProofError> {
+        if slice.len() != 64 {
+            return Err(ProofError::FormatError);
+        }
+
+        use util::read32;
+
+        let decompress = |chunk: &[u8]| {
+            CompressedRistretto(read32(chunk))
+                .decompress()
+                .ok_or(ProofError::FormatError)
+        };
+
+        Ok(PedersenGens {
+            B: decompress(&slice[..32])?,
+            B_blinding: decompress(&slice[32..])?,
+        })
+    }
 }
 
 impl Default for PedersenGens {
     fn default() -> Self {
-        PedersenGens {
-            B: GeneratorsChain::new(b"Bulletproofs.Generators.B")
-                .next()
-                .unwrap(),
-            B_blinding: GeneratorsChain::new(b"Bulletproofs.Generators.B_blinding")
-                .next()
-                .unwrap(),
-        }
+        PedersenGens::new_with_hasher::<Shake256>()
     }
 }
 
 /// The `GeneratorsChain` creates an arbitrary-long sequence of orthogonal generators.
-/// The sequence can be deterministically produced starting with an arbitrary point.
-struct GeneratorsChain {
-    reader: Sha3XofReader,
+/// The sequence can be deterministically produced starting with an arbitrary point,
+/// by reading from the extendable-output function `X`.
+struct GeneratorsChain<X: ExtendableOutput> {
+    reader: X::Reader,
 }
 
-impl GeneratorsChain {
+impl<X: ExtendableOutput + Input + Default> GeneratorsChain<X> {
     /// Creates a chain of generators, determined by the hash of `label`.
     fn new(label: &[u8]) -> Self {
-        let mut shake = Shake256::default();
-        shake.process(b"GeneratorsChain");
-        shake.process(label);
+        let mut xof = X::default();
+        xof.process(b"GeneratorsChain");
+        xof.process(label);
 
         GeneratorsChain {
-            reader: shake.xof_result(),
+            reader: xof.xof_result(),
+        }
+    }
+
+    /// Advances the reader `n` times, squeezing and discarding the
+    /// bytes that would have been used to produce `n` generators.
+    fn fast_forward(mut self, n: usize) -> Self {
+        for _ in 0..n {
+            let mut buf = [0u8; 64];
+            self.reader.read(&mut buf);
         }
+        self
     }
 }
 
-impl Default for GeneratorsChain {
+impl<X: ExtendableOutput + Input + Default> Default for GeneratorsChain<X> {
     fn default() -> Self {
         Self::new(&[])
     }
 }
 
-impl Iterator for GeneratorsChain {
+impl<X: ExtendableOutput> Iterator for GeneratorsChain<X> {
     type Item = RistrettoPoint;
 
     fn next(&mut self) -> Option<Self::Item> {
@@ -83,6 +155,11 @@ impl Iterator for GeneratorsChain {
 
 /// The `BulletproofGens` struct contains all the generators needed for
 /// aggregating `m` range proofs of `n` bits each.
+///
+/// The generators are reference-counted, so cloning a
+/// `BulletproofGens`, for instance to send it to another thread, does
+/// not copy them.  A single party's generators can be sent on their
+/// own as an [`OwnedBulletproofGensShare`].
 #[derive(Clone)]
 pub struct BulletproofGens {
     /// The maximum number of usable generators for each party.
@@ -90,9 +167,16 @@ pub struct BulletproofGens {
     /// Number of values or parties
     pub party_capacity: usize,
     /// Precomputed \\(\mathbf G\\) generators for each party.
-    G_vec: Vec<Vec<RistrettoPoint>>,
+    G_vec: Arc<Vec<Vec<RistrettoPoint>>>,
     /// Precomputed \\(\mathbf H\\) generators for each party.
-    H_vec: Vec<Vec<RistrettoPoint>>,
+    H_vec: Arc<Vec<Vec<RistrettoPoint>>>,
+    /// Whether some of the generators were supplied by the caller of
+    /// `from_generators`, rather than derived from the hash chains.
+    supplied: bool,
+    /// Derives the generators `start..end` of the hash chain with the
+    /// given label, using the extendable-output function chosen when
+    /// the generators were created.
+    derive: fn(&[u8], usize, usize) -> Vec<RistrettoPoint>,
 }
 
 impl BulletproofGens {
@@ -102,38 +186,266 @@ impl BulletproofGens {
     ///
     /// * `gens_capacity` is the number of generators to precompute
     ///    for each party.  For rangeproofs, it is sufficient to pass
-    ///    `64`, the maximum bitsize of the rangeproofs.  For circuit
+    ///    the largest bitsize to be proven, which is at most `128`.
+    ///    For circuit
     ///    proofs, the capacity must be greater than the number of
     ///    multipliers, rounded up to the next power of two.
     /// * `party_capacity` is the maximum number of parties that can
     ///    produce an aggregated proof.
     pub fn new(gens_capacity: usize, party_capacity: usize) -> Self {
-        use byteorder::{ByteOrder, LittleEndian};
+        BulletproofGens::new_with_hasher::<Shake256>(gens_capacity, party_capacity)
+    }
 
+    /// Create a new `BulletproofGens` object whose generators are
+    /// derived with the extendable-output function `X` instead of
+    /// SHAKE256.
+    ///
+    /// The generators are derived from the same labels as
+    /// [`BulletproofGens::new`], which is the same as
+    /// `BulletproofGens::new_with_hasher::<Shake256>`.
+    /// [`extend`](BulletproofGens::extend) continues the hash chains
+    /// with the same function.
+    ///
+    /// The encoding does not record `X`, so these generators can only
+    /// be parsed with
+    /// [`from_bytes_with_hasher`](BulletproofGens::from_bytes_with_hasher);
+    /// the serde `Deserialize` impl only accepts generators derived
+    /// with SHAKE256.
+    pub fn new_with_hasher<X>(gens_capacity: usize, party_capacity: usize) -> Self
+    where
+        X: ExtendableOutput + Input + Default,
+    {
         BulletproofGens {
+            gens_capacity: 0,
+            party_capacity: 0,
+            G_vec: Arc::new(Vec::new()),
+            H_vec: Arc::new(Vec::new()),
+            supplied: false,
+            derive: derive_gens::<X>,
+        }.extend(gens_capacity, party_capacity)
+    }
+
+    /// Creates a `BulletproofGens` object from generators chosen by
+    /// the caller, where `G_vec[j]` and `H_vec[j]` are the generators
+    /// of the `j`-th party.
+    ///
+    /// Returns an error unless there are as many \\(\mathbf H\\) as
+    /// \\(\mathbf G\\) vectors, and all of them have the same
+    /// length, which becomes the `gens_capacity`.
+    ///
+    /// # Security
+    ///
+    /// The caller is responsible for ensuring that nobody knows a
+    /// discrete log relation between any of the generators, or
+    /// between them and the Pedersen generators: anyone who knows one
+    /// can forge proofs.  Generators derived by hashing to the group,
+    /// as [`BulletproofGens::new`] does, have this property.
+    ///
+    /// [`extend`](BulletproofGens::extend) appends generators derived
+    /// from the same hash chains as `BulletproofGens::new`.
+    pub fn from_generators(
+        G_vec: Vec<Vec<RistrettoPoint>>,
+        H_vec: Vec<Vec<RistrettoPoint>>,
+    ) -> Result<BulletproofGens, ProofError> {
+        let party_capacity = G_vec.len();
+        let gens_capacity = G_vec.first().map(|G_j| G_j.len()).unwrap_or(0);
+
+        if H_vec.len() != party_capacity {
+            return Err(ProofError::InvalidGeneratorsLength {
+                expected: party_capacity,
+                actual: H_vec.len(),
+            });
+        }
+        if let Some(v) = G_vec
+            .iter()
+            .chain(H_vec.iter())
+            .find(|v| v.len() != gens_capacity)
+        {
+            return Err(ProofError::InvalidGeneratorsLength {
+                expected: gens_capacity,
+                actual: v.len(),
+            });
+        }
+
+        Ok(BulletproofGens {
             gens_capacity,
             party_capacity,
-            G_vec: (0..party_capacity)
-                .map(|i| {
-                    let party_index = i as u32;
-                    let mut label = [b'G', 0, 0, 0, 0];
-                    LittleEndian::write_u32(&mut label[1..5], party_index);
-
-                    GeneratorsChain::new(&label)
-                        .take(gens_capacity)
-                        .collect::<Vec<_>>()
-                }).collect(),
-            H_vec: (0..party_capacity)
-                .map(|i| {
-                    let party_index = i as u32;
-                    let mut label = [b'H', 0, 0, 0, 0];
-                    LittleEndian::write_u32(&mut label[1..5], party_index);
-
-                    GeneratorsChain::new(&label)
-                        .take(gens_capacity)
-                        .collect::<Vec<_>>()
-                }).collect(),
+            G_vec: Arc::new(G_vec),
+            H_vec: Arc::new(H_vec),
+            supplied: true,
+            derive: derive_gens::<Shake256>,
+        })
+    }
+
+    /// Grows the generators to the given capacities, keeping the
+    /// existing generators.
+    ///
+    /// The new generators continue the hash chains of the existing
+    /// ones, so the result is the same as creating the generators
+    /// with [`BulletproofGens::new`] at the larger capacities, and
+    /// proofs created with the smaller generators still verify with
+    /// the extended ones.  A capacity smaller than the current one
+    /// leaves that capacity unchanged.
+    pub fn extend(mut self, gens_capacity: usize, party_capacity: usize) -> BulletproofGens {
+        use core::cmp;
+
+        let gens_capacity = cmp::max(gens_capacity, self.gens_capacity);
+        let party_capacity = cmp::max(party_capacity, self.party_capacity);
+
+        // Copies the generators if they are shared with a clone.
+        let G_vec = Arc::make_mut(&mut self.G_vec);
+        let H_vec = Arc::make_mut(&mut self.H_vec);
+
+        G_vec.resize(party_capacity, Vec::new());
+        H_vec.resize(party_capacity, Vec::new());
+
+        for (i, G_i) in G_vec.iter_mut().enumerate() {
+            let len = G_i.len();
+            G_i.extend((self.derive)(&party_label(b'G', i), len, gens_capacity));
+        }
+        for (i, H_i) in H_vec.iter_mut().enumerate() {
+            let len = H_i.len();
+            H_i.extend((self.derive)(&party_label(b'H', i), len, gens_capacity));
+        }
+
+        self.gens_capacity = gens_capacity;
+        self.party_capacity = party_capacity;
+        self
+    }
+
+    /// Serializes the generators into a byte array.
+    ///
+    /// # Layout
+    ///
+    /// The layout of the generators encoding is:
+    ///
+    /// * the `gens_capacity` and `party_capacity`, as 8-byte
+    ///   little-endian integers,
+    /// * a byte which is `1` if the generators were supplied with
+    ///   [`from_generators`](BulletproofGens::from_generators), and
+    ///   `0` otherwise; only
+    ///   [`from_bytes_unchecked`](BulletproofGens::from_bytes_unchecked)
+    ///   accepts an encoding with this byte set,
+    /// * the compressed \\(\mathbf G\\) generators of each party in
+    ///   turn, `gens_capacity` per party,
+    /// * the compressed \\(\mathbf H\\) generators, in the same order.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(17 + 64 * self.gens_capacity * self.party_capacity);
+        let mut header = [0u8; 17];
+        LittleEndian::write_u64(&mut header[0..8], self.gens_capacity as u64);
+        LittleEndian::write_u64(&mut header[8..16], self.party_capacity as u64);
+        header[16] = self.supplied as u8;
+        buf.extend_from_slice(&header);
+        for P in self.G_vec.iter().chain(self.H_vec.iter()).flat_map(|v| v.iter()) {
+            buf.extend_from_slice(P.compress().as_bytes());
+        }
+        buf
+    }
+
+    /// Deserializes the generators from a byte slice.
+    ///
+    /// Returns an error if the byte slice cannot be parsed into
+    /// `BulletproofGens`, or if any of the generators differ from the
+    /// ones [`BulletproofGens::new`] would produce.  Every generator
+    /// is re-derived and compared, so a tampered encoding is rejected
+    /// rather than producing generators with a known discrete log
+    /// relation.  This makes it suitable for bytes from an untrusted
+    /// source, such as a cached file, at the cost of deriving the
+    /// generators again.
+    ///
+    /// Generators supplied with
+    /// [`from_generators`](BulletproofGens::from_generators) cannot be
+    /// re-derived, so they are rejected.  Trusted bytes, including
+    /// supplied generators, can be parsed without the check by
+    /// [`from_bytes_unchecked`](BulletproofGens::from_bytes_unchecked).
+    ///
+    /// Generators created with
+    /// [`new_with_hasher`](BulletproofGens::new_with_hasher) must be
+    /// parsed with
+    /// [`from_bytes_with_hasher`](BulletproofGens::from_bytes_with_hasher).
+    pub fn from_bytes(slice: &[u8]) -> Result<BulletproofGens, ProofError> {
+        BulletproofGens::from_bytes_with_hasher::<Shake256>(slice)
+    }
+
+    /// Deserializes generators which were derived with the
+    /// extendable-output function `X`, checking them as
+    /// [`from_bytes`](BulletproofGens::from_bytes) does.
+    pub fn from_bytes_with_hasher<X>(slice: &[u8]) -> Result<BulletproofGens, ProofError>
+    where
+        X: ExtendableOutput + Input + Default,
+    {
+        let (gens_capacity, party_capacity, supplied) = read_header(slice)?;
+        if supplied {
+            return Err(ProofError::FormatError);
+        }
+
+        // The generators have to be derived to check them, so compare
+        // the encodings rather than also decompressing the points.
+        let gens = BulletproofGens::new_with_hasher::<X>(gens_capacity, party_capacity);
+        if gens.to_bytes()[..] != slice[..] {
+            return Err(ProofError::FormatError);
         }
+        Ok(gens)
+    }
+
+    /// Deserializes the generators from a byte slice without checking
+    /// that they were derived from the hash chains.
+    ///
+    /// This only checks that the encoding is well-formed, so it is
+    /// cheaper than [`from_bytes`](BulletproofGens::from_bytes), but
+    /// the generators are trusted as if they were passed to
+    /// [`from_generators`](BulletproofGens::from_generators).  It must
+    /// not be used for bytes from an untrusted source, since anyone
+    /// who can change them can forge proofs.
+    pub fn from_bytes_unchecked(slice: &[u8]) -> Result<BulletproofGens, ProofError> {
+        let (gens_capacity, party_capacity, supplied) = read_header(slice)?;
+
+        use util::read32;
+
+        let mut points = slice[17..].chunks(32).map(|chunk| {
+            CompressedRistretto(read32(chunk))
+                .decompress()
+                .ok_or(ProofError::FormatError)
+        });
+        let mut read_gens = || {
+            (0..party_capacity)
+                .map(|_| points.by_ref().take(gens_capacity).collect())
+                .collect::<Result<Vec<Vec<_>>, _>>()
+        };
+        let G_vec = read_gens()?;
+        let H_vec = read_gens()?;
+
+        Ok(BulletproofGens {
+            gens_capacity,
+            party_capacity,
+            G_vec: Arc::new(G_vec),
+            H_vec: Arc::new(H_vec),
+            supplied,
+            derive: derive_gens::<Shake256>,
+        })
+    }
+
+    /// Returns the largest rangeproof bitsize \\(n\\) these
+    /// generators support, or `0` if the `gens_capacity` is smaller
+    /// than every supported bitsize.
+    pub fn max_bitsize(&self) -> usize {
+        [128, 64, 32, 16, 8]
+            .iter()
+            .cloned()
+            .find(|&n| n <= self.gens_capacity)
+            .unwrap_or(0)
+    }
+
+    /// Returns the maximum number of parties in an aggregated proof,
+    /// that is, the `party_capacity`.
+    pub fn max_parties(&self) -> usize {
+        self.party_capacity
+    }
+
+    /// Returns the total number of precomputed \\(\mathbf G\\) and
+    /// \\(\mathbf H\\) generators.
+    pub fn total_generators(&self) -> usize {
+        2 * self.gens_capacity * self.party_capacity
     }
 
     /// Returns j-th share of generators, with an appropriate
@@ -145,61 +457,308 @@ impl BulletproofGens {
         }
     }
 
-    /// Return an iterator over the aggregation of the parties' G generators with given size `n`.
-    pub(crate) fn G(&self, n: usize, m: usize) -> impl Iterator<Item = &RistrettoPoint> {
-        AggregatedGensIter {
-            n,
-            m,
-            array: &self.G_vec,
-            party_idx: 0,
-            gen_idx: 0,
+    /// Returns the j-th share of generators like
+    /// [`share`](BulletproofGens::share), but holding its own handle
+    /// to the generator tables, so that it can be sent to another
+    /// thread without borrowing `self`.  The tables are not copied.
+    pub fn owned_share(&self, j: usize) -> OwnedBulletproofGensShare {
+        OwnedBulletproofGensShare {
+            G_vec: self.G_vec.clone(),
+            H_vec: self.H_vec.clone(),
+            share: j,
         }
     }
 
+    /// Returns the shares of generators for parties which use
+    /// different numbers of generators, where the `j`-th party uses
+    /// `party_sizes[j]` of them, for instance parties proving ranges
+    /// of different bitsizes.
+    ///
+    /// Each party draws from its own generators, so the sizes don't
+    /// need to add up to anything: the `j`-th share holds the first
+    /// `party_sizes[j]` generators of the `j`-th party, and the
+    /// aggregated generators are the concatenation of the shares.
+    ///
+    /// Returns an error if a size exceeds the `gens_capacity`, or if
+    /// there are more sizes than the `party_capacity`.
+    pub fn share_uneven(
+        &self,
+        party_sizes: &[usize],
+    ) -> Result<Vec<BulletproofGensShare>, ProofError> {
+        if let Some(&needed) = party_sizes.iter().max() {
+            if needed > self.gens_capacity {
+                return Err(ProofError::InsufficientGeneratorCapacity {
+                    needed,
+                    available: self.gens_capacity,
+                });
+            }
+        }
+        if party_sizes.len() > self.party_capacity {
+            return Err(ProofError::InsufficientPartyCapacity {
+                needed: party_sizes.len(),
+                available: self.party_capacity,
+            });
+        }
+
+        Ok((0..party_sizes.len()).map(|j| self.share(j)).collect())
+    }
+
+    /// Return an iterator over the aggregation of the parties' G generators with given size `n`.
+    ///
+    /// The iterator yields the first `n` generators of party 0, then
+    /// of party 1, and so on up to party `m-1`, which is the order
+    /// used by an aggregated proof.  `n` and `m` must not exceed the
+    /// `gens_capacity` and `party_capacity`.
+    pub fn G(&self, n: usize, m: usize) -> AggregatedGensIter {
+        AggregatedGensIter::new(&self.G_vec, n, m)
+    }
+
     /// Return an iterator over the aggregation of the parties' H generators with given size `n`.
-    pub(crate) fn H(&self, n: usize, m: usize) -> impl Iterator<Item = &RistrettoPoint> {
-        AggregatedGensIter {
-            n,
-            m,
-            array: &self.H_vec,
-            party_idx: 0,
-            gen_idx: 0,
+    ///
+    /// The generators are in the same order as for
+    /// [`G`](BulletproofGens::G).
+    pub fn H(&self, n: usize, m: usize) -> AggregatedGensIter {
+        AggregatedGensIter::new(&self.H_vec, n, m)
+    }
+
+    /// Return an iterator over the aggregation of the parties' G
+    /// generators, where the `j`-th party uses `bitsizes[j]` of them.
+    pub(crate) fn G_with_bitsizes<'a>(
+        &'a self,
+        bitsizes: &'a [usize],
+    ) -> impl Iterator<Item = &'a RistrettoPoint> + 'a {
+        bitsizes
+            .iter()
+            .zip(self.G_vec.iter())
+            .flat_map(|(&n_j, G_j)| G_j.iter().take(n_j))
+    }
+
+    /// Return an iterator over the aggregation of the parties' H
+    /// generators, where the `j`-th party uses `bitsizes[j]` of them.
+    pub(crate) fn H_with_bitsizes<'a>(
+        &'a self,
+        bitsizes: &'a [usize],
+    ) -> impl Iterator<Item = &'a RistrettoPoint> + 'a {
+        bitsizes
+            .iter()
+            .zip(self.H_vec.iter())
+            .flat_map(|(&n_j, H_j)| H_j.iter().take(n_j))
+    }
+}
+
+/// Derives the generators `start..end` of the hash chain with the
+/// given `label`, using the extendable-output function `X`.
+fn derive_gens<X>(label: &[u8], start: usize, end: usize) -> Vec<RistrettoPoint>
+where
+    X: ExtendableOutput + Input + Default,
+{
+    GeneratorsChain::<X>::new(label)
+        .fast_forward(start)
+        .take(end - start)
+        .collect()
+}
+
+/// Parses the header of a `BulletproofGens` encoding, returning the
+/// `gens_capacity`, the `party_capacity` and whether the generators
+/// were supplied, after checking that `slice` has the length the
+/// capacities require.
+fn read_header(slice: &[u8]) -> Result<(usize, usize, bool), ProofError> {
+    if slice.len() < 17 {
+        return Err(ProofError::FormatError);
+    }
+    let gens_capacity = LittleEndian::read_u64(&slice[0..8]) as usize;
+    let party_capacity = LittleEndian::read_u64(&slice[8..16]) as usize;
+    let supplied = match slice[16] {
+        0 => false,
+        1 => true,
+        _ => return Err(ProofError::FormatError),
+    };
+
+    let expected_len = gens_capacity
+        .checked_mul(party_capacity)
+        .and_then(|num_points| num_points.checked_mul(64))
+        .and_then(|len| len.checked_add(17))
+        .ok_or(ProofError::FormatError)?;
+    if slice.len() != expected_len {
+        return Err(ProofError::FormatError);
+    }
+
+    Ok((gens_capacity, party_capacity, supplied))
+}
+
+/// Returns the label of the hash chain of the `i`-th party's
+/// generators, where `prefix` is `b'G'` or `b'H'`.
+fn party_label(prefix: u8, i: usize) -> [u8; 5] {
+    let mut label = [prefix, 0, 0, 0, 0];
+    LittleEndian::write_u32(&mut label[1..5], i as u32);
+    label
+}
+
+/// Two `BulletproofGens` are equal if they have the same capacities
+/// and the same generators, compared in constant time.  How the
+/// generators were derived, which determines how they
+/// [`extend`](BulletproofGens::extend), is not compared.
+impl PartialEq for BulletproofGens {
+    fn eq(&self, other: &BulletproofGens) -> bool {
+        if self.gens_capacity != other.gens_capacity
+            || self.party_capacity != other.party_capacity
+        {
+            return false;
+        }
+
+        fn points(gens: &BulletproofGens) -> impl Iterator<Item = &RistrettoPoint> {
+            gens.G_vec
+                .iter()
+                .chain(gens.H_vec.iter())
+                .flat_map(|v| v.iter())
+        }
+        let mut equal = Choice::from(1u8);
+        for (P, Q) in points(self).zip(points(other)) {
+            equal &= P.ct_eq(Q);
+        }
+        equal.into()
+    }
+}
+
+impl Eq for BulletproofGens {}
+
+impl Serialize for PedersenGens {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_bytes(&self.to_bytes()[..])
+    }
+}
+
+impl<'de> Deserialize<'de> for PedersenGens {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct PedersenGensVisitor;
+
+        impl<'de> Visitor<'de> for PedersenGensVisitor {
+            type Value = PedersenGens;
+
+            fn expecting(&self, formatter: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                formatter.write_str("valid PedersenGens")
+            }
+
+            fn visit_bytes<E>(self, v: &[u8]) -> Result<PedersenGens, E>
+            where
+                E: serde::de::Error,
+            {
+                PedersenGens::from_bytes(v).map_err(serde::de::Error::custom)
+            }
+        }
+
+        deserializer.deserialize_bytes(PedersenGensVisitor)
+    }
+}
+
+impl Serialize for BulletproofGens {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_bytes(&self.to_bytes()[..])
+    }
+}
+
+/// Deserializes generators with [`BulletproofGens::from_bytes`], so
+/// every generator is checked and the input need not be trusted.
+/// Only generators derived with the default SHAKE256 hash chain are
+/// accepted: generators derived with another hasher must be parsed
+/// with [`BulletproofGens::from_bytes_with_hasher`], and supplied
+/// generators with [`BulletproofGens::from_bytes_unchecked`].
+impl<'de> Deserialize<'de> for BulletproofGens {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct BulletproofGensVisitor;
+
+        impl<'de> Visitor<'de> for BulletproofGensVisitor {
+            type Value = BulletproofGens;
+
+            fn expecting(&self, formatter: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                formatter.write_str("valid BulletproofGens")
+            }
+
+            fn visit_bytes<E>(self, v: &[u8]) -> Result<BulletproofGens, E>
+            where
+                E: serde::de::Error,
+            {
+                BulletproofGens::from_bytes(v).map_err(serde::de::Error::custom)
+            }
         }
+
+        deserializer.deserialize_bytes(BulletproofGensVisitor)
     }
 }
 
-struct AggregatedGensIter<'a> {
+/// An iterator over the aggregation of the parties' generators,
+/// returned by [`BulletproofGens::G`] and [`BulletproofGens::H`].
+#[derive(Clone)]
+pub struct AggregatedGensIter<'a> {
     array: &'a Vec<Vec<RistrettoPoint>>,
     n: usize,
-    m: usize,
-    party_idx: usize,
-    gen_idx: usize,
+    /// Position of the next generator from the front, in the
+    /// concatenation of the parties' first `n` generators
+    front: usize,
+    /// Position after the next generator from the back
+    back: usize,
+}
+
+impl<'a> AggregatedGensIter<'a> {
+    fn new(array: &'a Vec<Vec<RistrettoPoint>>, n: usize, m: usize) -> Self {
+        AggregatedGensIter {
+            array,
+            n,
+            front: 0,
+            back: n * m,
+        }
+    }
+
+    fn get(&self, i: usize) -> &'a RistrettoPoint {
+        &self.array[i / self.n][i % self.n]
+    }
 }
 
 impl<'a> Iterator for AggregatedGensIter<'a> {
     type Item = &'a RistrettoPoint;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.gen_idx >= self.n {
-            self.gen_idx = 0;
-            self.party_idx += 1;
-        }
-
-        if self.party_idx >= self.m {
+        if self.front >= self.back {
             None
         } else {
-            let cur_gen = self.gen_idx;
-            self.gen_idx += 1;
-            Some(&self.array[self.party_idx][cur_gen])
+            self.front += 1;
+            Some(self.get(self.front - 1))
         }
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
-        let size = self.n * self.m;
+        let size = self.back - self.front;
         (size, Some(size))
     }
 }
 
+impl<'a> DoubleEndedIterator for AggregatedGensIter<'a> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front >= self.back {
+            None
+        } else {
+            self.back -= 1;
+            Some(self.get(self.back))
+        }
+    }
+}
+
+impl<'a> ExactSizeIterator for AggregatedGensIter<'a> {}
+
+impl<'a> FusedIterator for AggregatedGensIter<'a> {}
+
 /// The `BulletproofGensShare` is produced by `BulletproofGens::share()`.
 ///
 /// The `BulletproofGens` struct represents generators for an aggregated
@@ -215,13 +774,53 @@ pub struct BulletproofGensShare<'a> {
 
 impl<'a> BulletproofGensShare<'a> {
     /// Return an iterator over this party's G generators with given size `n`.
-    pub(crate) fn G(&self, n: usize) -> impl Iterator<Item = &'a RistrettoPoint> {
-        self.gens.G_vec[self.share].iter().take(n)
+    ///
+    /// The iterator yields at most `gens_capacity` generators.
+    pub fn G(&self, n: usize) -> slice::Iter<'a, RistrettoPoint> {
+        let G_j = &self.gens.G_vec[self.share];
+        G_j[..n.min(G_j.len())].iter()
+    }
+
+    /// Return an iterator over this party's H generators with given size `n`.
+    ///
+    /// The iterator yields at most `gens_capacity` generators.
+    pub fn H(&self, n: usize) -> slice::Iter<'a, RistrettoPoint> {
+        let H_j = &self.gens.H_vec[self.share];
+        H_j[..n.min(H_j.len())].iter()
+    }
+}
+
+/// An owned share of generators, produced by
+/// [`BulletproofGens::owned_share`].
+///
+/// It shares the generator tables of the `BulletproofGens` it was
+/// created from, and yields the same generators as the corresponding
+/// [`BulletproofGensShare`], but is `'static` and `Send`.
+#[derive(Clone)]
+pub struct OwnedBulletproofGensShare {
+    /// The parties' \\(\mathbf G\\) generators
+    G_vec: Arc<Vec<Vec<RistrettoPoint>>>,
+    /// The parties' \\(\mathbf H\\) generators
+    H_vec: Arc<Vec<Vec<RistrettoPoint>>>,
+    /// Which share we are
+    share: usize,
+}
+
+impl OwnedBulletproofGensShare {
+    /// Return an iterator over this party's G generators with given size `n`.
+    ///
+    /// The iterator yields at most `gens_capacity` generators.
+    pub fn G(&self, n: usize) -> slice::Iter<RistrettoPoint> {
+        let G_j = &self.G_vec[self.share];
+        G_j[..n.min(G_j.len())].iter()
     }
 
     /// Return an iterator over this party's H generators with given size `n`.
-    pub(crate) fn H(&self, n: usize) -> impl Iterator<Item = &'a RistrettoPoint> {
-        self.gens.H_vec[self.share].iter().take(n)
+    ///
+    /// The iterator yields at most `gens_capacity` generators.
+    pub fn H(&self, n: usize) -> slice::Iter<RistrettoPoint> {
+        let H_j = &self.H_vec[self.share];
+        H_j[..n.min(H_j.len())].iter()
     }
 }
 
@@ -230,6 +829,17 @@ mod tests {
     extern crate hex;
     use super::*;
 
+    #[test]
+    fn capacity_accessors() {
+        let gens = BulletproofGens::new(64, 4);
+        assert_eq!(gens.max_bitsize(), 64);
+        assert_eq!(gens.max_parties(), 4);
+        assert_eq!(gens.total_generators(), 2 * 64 * 4);
+
+        assert_eq!(BulletproofGens::new(100, 1).max_bitsize(), 64);
+        assert_eq!(BulletproofGens::new(4, 1).max_bitsize(), 0);
+    }
+
     #[test]
     fn aggregated_gens_iter_matches_flat_map() {
         let gens = BulletproofGens::new(64, 8);
@@ -270,4 +880,422 @@ mod tests {
         helper(16, 2);
         helper(16, 1);
     }
+
+    #[test]
+    fn gens_with_bitsizes_matches_shares() {
+        let gens = BulletproofGens::new(64, 4);
+        let bitsizes = [8, 32, 64, 16];
+
+        let agg_G: Vec<RistrettoPoint> = gens.G_with_bitsizes(&bitsizes).cloned().collect();
+        let agg_H: Vec<RistrettoPoint> = gens.H_with_bitsizes(&bitsizes).cloned().collect();
+
+        let share_G: Vec<RistrettoPoint> = bitsizes
+            .iter()
+            .enumerate()
+            .flat_map(|(j, &n_j)| gens.share(j).G(n_j))
+            .cloned()
+            .collect();
+        let share_H: Vec<RistrettoPoint> = bitsizes
+            .iter()
+            .enumerate()
+            .flat_map(|(j, &n_j)| gens.share(j).H(n_j))
+            .cloned()
+            .collect();
+
+        assert_eq!(agg_G.len(), 120);
+        assert_eq!(agg_G, share_G);
+        assert_eq!(agg_H, share_H);
+    }
+
+    #[test]
+    fn extended_gens_match_new() {
+        let small = BulletproofGens::new(16, 2);
+        let extended = small.clone().extend(64, 4);
+        let big = BulletproofGens::new(64, 4);
+
+        assert_eq!(extended.gens_capacity, 64);
+        assert_eq!(extended.party_capacity, 4);
+        assert_eq!(extended.G_vec, big.G_vec);
+        assert_eq!(extended.H_vec, big.H_vec);
+        assert_eq!(small.G_vec[1][..], extended.G_vec[1][..16]);
+
+        // Smaller capacities don't shrink the generators
+        let same = big.clone().extend(8, 1);
+        assert_eq!(same.gens_capacity, 64);
+        assert_eq!(same.party_capacity, 4);
+        assert_eq!(same.G_vec, big.G_vec);
+    }
+
+    #[test]
+    fn gens_equality() {
+        use sha3::Shake128;
+
+        let gens = BulletproofGens::new(16, 2);
+        assert!(gens == BulletproofGens::new(16, 2));
+        assert!(gens == BulletproofGens::from_bytes(&gens.to_bytes()).unwrap());
+        assert!(gens == BulletproofGens::new(8, 1).extend(16, 2));
+
+        assert!(gens != BulletproofGens::new_with_hasher::<Shake128>(16, 2));
+        assert!(gens != BulletproofGens::new(16, 4));
+        assert!(gens != BulletproofGens::new(8, 2));
+
+        // Swapping the G and H tables gives different generators
+        let swapped = BulletproofGens::from_generators(
+            (*gens.H_vec).clone(),
+            (*gens.G_vec).clone(),
+        ).unwrap();
+        assert!(gens != swapped);
+    }
+
+    #[test]
+    fn gens_bytes_roundtrip() {
+        use bincode;
+
+        let gens = BulletproofGens::new(16, 4);
+        let bytes = gens.to_bytes();
+        assert_eq!(bytes.len(), 17 + 2 * 16 * 4 * 32);
+
+        let parsed = BulletproofGens::from_bytes(&bytes).unwrap();
+        assert_eq!(parsed.gens_capacity, 16);
+        assert_eq!(parsed.party_capacity, 4);
+        assert_eq!(parsed.G_vec, gens.G_vec);
+        assert_eq!(parsed.H_vec, gens.H_vec);
+
+        let serialized = bincode::serialize(&gens).unwrap();
+        let deserialized: BulletproofGens = bincode::deserialize(&serialized).unwrap();
+        assert_eq!(deserialized.G_vec, gens.G_vec);
+        assert_eq!(deserialized.H_vec, gens.H_vec);
+
+        let empty = BulletproofGens::new(0, 0);
+        assert!(BulletproofGens::from_bytes(&empty.to_bytes()).is_ok());
+    }
+
+    #[test]
+    fn corrupted_gens_are_rejected() {
+        let gens = BulletproofGens::new(16, 4);
+        let bytes = gens.to_bytes();
+
+        // Truncated
+        assert!(BulletproofGens::from_bytes(&bytes[..bytes.len() - 32]).is_err());
+        assert!(BulletproofGens::from_bytes(&bytes[..8]).is_err());
+
+        // Capacity doesn't match the number of points
+        let mut bad_bytes = bytes.clone();
+        bad_bytes[0] = 8;
+        assert!(BulletproofGens::from_bytes(&bad_bytes).is_err());
+
+        // A G generator, an H generator in the middle of the table,
+        // or the last H generator is replaced by another valid point
+        let other = gens.H_vec[0][0].compress();
+        let G_pos = 17 + 32 * (16 + 5);
+        let H_pos = 17 + 32 * (16 * 4 + 16 * 2 + 9);
+        for &pos in &[G_pos, H_pos, bytes.len() - 32] {
+            let mut bad_bytes = bytes.clone();
+            bad_bytes[pos..pos + 32].copy_from_slice(other.as_bytes());
+            assert!(BulletproofGens::from_bytes(&bad_bytes).is_err());
+
+            // The unchecked loader only checks the encoding
+            let parsed = BulletproofGens::from_bytes_unchecked(&bad_bytes).unwrap();
+            assert!(parsed != gens);
+        }
+        assert!(BulletproofGens::from_bytes_unchecked(&bytes).unwrap() == gens);
+
+        // A point which is not a valid encoding
+        let mut bad_bytes = bytes.clone();
+        for b in bad_bytes[17..49].iter_mut() {
+            *b = 0xff;
+        }
+        assert!(BulletproofGens::from_bytes(&bad_bytes).is_err());
+    }
+
+    #[test]
+    fn pedersen_gens_bytes_roundtrip() {
+        use bincode;
+
+        let gens = PedersenGens::default();
+        let parsed = PedersenGens::from_bytes(&gens.to_bytes()).unwrap();
+        assert_eq!(parsed.B, gens.B);
+        assert_eq!(parsed.B_blinding, gens.B_blinding);
+
+        let serialized = bincode::serialize(&gens).unwrap();
+        let deserialized: PedersenGens = bincode::deserialize(&serialized).unwrap();
+        assert_eq!(deserialized.B, gens.B);
+        assert_eq!(deserialized.B_blinding, gens.B_blinding);
+
+        // Truncated
+        let bytes = gens.to_bytes();
+        assert!(PedersenGens::from_bytes(&bytes[..32]).is_err());
+
+        // A point which is not a valid encoding
+        let mut bad_bytes = bytes;
+        for b in bad_bytes[32..].iter_mut() {
+            *b = 0xff;
+        }
+        assert!(PedersenGens::from_bytes(&bad_bytes).is_err());
+        let encoded = bincode::serialize(&bad_bytes[..]).unwrap();
+        assert!(bincode::deserialize::<PedersenGens>(&encoded).is_err());
+    }
+
+    #[test]
+    fn supplied_gens() {
+        use bincode;
+
+        let G: Vec<Vec<RistrettoPoint>> = (0..2)
+            .map(|j| GeneratorsChain::<Shake256>::new(&[b'g', j]).take(8).collect())
+            .collect();
+        let H: Vec<Vec<RistrettoPoint>> = (0..2)
+            .map(|j| GeneratorsChain::<Shake256>::new(&[b'h', j]).take(8).collect())
+            .collect();
+
+        let gens = BulletproofGens::from_generators(G.clone(), H.clone()).unwrap();
+        assert_eq!(gens.gens_capacity, 8);
+        assert_eq!(gens.party_capacity, 2);
+        assert_eq!(gens.share(1).G(8).cloned().collect::<Vec<_>>(), G[1]);
+        assert_eq!(gens.share(1).H(8).cloned().collect::<Vec<_>>(), H[1]);
+
+        // Supplied generators can't be re-derived, so only the
+        // unchecked loader accepts them
+        let bytes = gens.to_bytes();
+        assert!(BulletproofGens::from_bytes(&bytes).is_err());
+        let serialized = bincode::serialize(&gens).unwrap();
+        assert!(bincode::deserialize::<BulletproofGens>(&serialized).is_err());
+
+        let parsed = BulletproofGens::from_bytes_unchecked(&bytes).unwrap();
+        assert_eq!(*parsed.G_vec, G);
+        assert_eq!(*parsed.H_vec, H);
+
+        // Marking derived generators as supplied doesn't skip the check
+        let mut bytes = BulletproofGens::new(8, 2).to_bytes();
+        bytes[16] = 1;
+        assert!(BulletproofGens::from_bytes(&bytes).is_err());
+
+        // Mismatched lengths are rejected
+        assert!(BulletproofGens::from_generators(G.clone(), H[..1].to_vec()).is_err());
+        let mut short_H = H.clone();
+        short_H[1].pop();
+        assert!(BulletproofGens::from_generators(G.clone(), short_H).is_err());
+    }
+
+    #[test]
+    fn uneven_shares_match_gens_with_bitsizes() {
+        let gens = BulletproofGens::new(64, 4);
+        let party_sizes = [64, 8, 32];
+
+        let shares = gens.share_uneven(&party_sizes).unwrap();
+        assert_eq!(shares.len(), 3);
+
+        let share_G: Vec<RistrettoPoint> = shares
+            .iter()
+            .zip(party_sizes.iter())
+            .flat_map(|(share, &n_j)| share.G(n_j))
+            .cloned()
+            .collect();
+        let agg_G: Vec<RistrettoPoint> = gens.G_with_bitsizes(&party_sizes).cloned().collect();
+        assert_eq!(share_G, agg_G);
+
+        assert_eq!(
+            gens.share_uneven(&[8, 128]).err(),
+            Some(ProofError::InsufficientGeneratorCapacity {
+                needed: 128,
+                available: 64
+            })
+        );
+        assert_eq!(
+            gens.share_uneven(&[8; 5]).err(),
+            Some(ProofError::InsufficientPartyCapacity {
+                needed: 5,
+                available: 4
+            })
+        );
+    }
+
+    #[test]
+    fn gens_with_hasher() {
+        use sha3::Shake128;
+
+        fn hex_of(point: &RistrettoPoint) -> String {
+            hex::encode(point.compress().as_bytes())
+        }
+
+        // Pinned first points of the chains, so that any change to the
+        // derivation is caught.
+        let pc_gens = PedersenGens::default();
+        assert_eq!(
+            hex_of(&pc_gens.B),
+            "00e882690c2653a88367fb58ead8133c844a4caf3f7f40cdd01702df58bfbb19"
+        );
+        assert_eq!(
+            hex_of(&pc_gens.B_blinding),
+            "c6ad2393cabcd6a6e816bbee3f2a763ec2c1774c6c9a691d8e55dd298134a563"
+        );
+        let gens = BulletproofGens::new(8, 2);
+        assert_eq!(
+            hex_of(&gens.G_vec[1][0]),
+            "0eeebec183d151ded1e24320cf43c987617b36e77114788e5ae8ace41570b74b"
+        );
+        assert_eq!(
+            hex_of(&gens.H_vec[0][0]),
+            "ba698f6dd08c501e32b55d2ee7259f6019d629fa2ba4d7039c5de157cba4df73"
+        );
+
+        let pc_gens_128 = PedersenGens::new_with_hasher::<Shake128>();
+        assert_eq!(
+            hex_of(&pc_gens_128.B),
+            "aefce59be9741c0bb08e2e85479cdd29b05325ad87aedffbef1db4152d92d61b"
+        );
+        assert_eq!(
+            hex_of(&pc_gens_128.B_blinding),
+            "b46dcd5deef21a06d2e6634d12f7568e69f62b19d59ba91c0ffa1ffed9784231"
+        );
+
+        let gens_128 = BulletproofGens::new_with_hasher::<Shake128>(4, 1).extend(8, 2);
+        assert_eq!(
+            hex_of(&gens_128.G_vec[1][0]),
+            "eede1decded903c1c38c31cc28f2c5bec756562f6d5698d239eb0b8ee38ecf54"
+        );
+        assert_eq!(
+            hex_of(&gens_128.H_vec[0][0]),
+            "8ae34af6af63f11f21eabb4e42ba393e23ed90466e656f951f9313a7a265bd1b"
+        );
+        assert_eq!(
+            gens_128.G_vec,
+            BulletproofGens::new_with_hasher::<Shake128>(8, 2).G_vec
+        );
+        assert!(gens_128.G_vec != gens.G_vec);
+
+        // The bytes are checked against the chosen hasher
+        let bytes = gens_128.to_bytes();
+        assert!(BulletproofGens::from_bytes(&bytes).is_err());
+        let parsed = BulletproofGens::from_bytes_with_hasher::<Shake128>(&bytes).unwrap();
+        assert_eq!(parsed.H_vec, gens_128.H_vec);
+
+        // serde only supports the default hasher
+        use bincode;
+        let serialized = bincode::serialize(&gens_128).unwrap();
+        assert!(bincode::deserialize::<BulletproofGens>(&serialized).is_err());
+        let serialized = bincode::serialize(&gens).unwrap();
+        let deserialized: BulletproofGens = bincode::deserialize(&serialized).unwrap();
+        assert_eq!(deserialized, gens);
+    }
+
+    #[test]
+    fn cloned_gens_share_tables_across_threads() {
+        use merlin::Transcript;
+        use range_proof::RangeProof;
+        use std::thread;
+        use util;
+
+        let mut rng = util::deterministic_rng([60u8; 32]);
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 1);
+
+        let (proof, V) = RangeProof::prove_single_with_rng(
+            &bp_gens,
+            &pc_gens,
+            &mut Transcript::new(b"ThreadTest"),
+            1037578891,
+            &Scalar::from(7u64),
+            64,
+            &mut rng,
+        ).unwrap();
+
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let bp_gens = bp_gens.clone();
+                let proof = proof.clone();
+                thread::spawn(move || {
+                    let mut rng = util::deterministic_rng([61u8; 32]);
+                    let mut transcript = Transcript::new(b"ThreadTest");
+                    proof
+                        .verify_single_with_rng(
+                            &bp_gens,
+                            &pc_gens,
+                            &mut transcript,
+                            &V,
+                            64,
+                            &mut rng
+                        ).is_ok()
+                })
+            }).collect();
+        for handle in handles {
+            assert!(handle.join().unwrap());
+        }
+
+        // Cloning doesn't copy the tables, but extending a clone does
+        let clone = bp_gens.clone();
+        assert!(Arc::ptr_eq(&clone.G_vec, &bp_gens.G_vec));
+        assert!(Arc::ptr_eq(&clone.H_vec, &bp_gens.H_vec));
+        let extended = clone.extend(64, 2);
+        assert!(!Arc::ptr_eq(&extended.G_vec, &bp_gens.G_vec));
+        assert_eq!(bp_gens.party_capacity, 1);
+        assert_eq!(extended.G_vec[0], bp_gens.G_vec[0]);
+    }
+
+    #[test]
+    fn owned_shares_move_across_threads() {
+        use std::thread;
+
+        fn assert_send_static<T: Send + 'static>(_: &T) {}
+
+        let gens = BulletproofGens::new(16, 4);
+
+        let handles: Vec<_> = (0..4)
+            .map(|j| {
+                let share = gens.owned_share(j);
+                assert_send_static(&share);
+                assert!(Arc::ptr_eq(&share.G_vec, &gens.G_vec));
+                assert!(Arc::ptr_eq(&share.H_vec, &gens.H_vec));
+                thread::spawn(move || {
+                    let G: Vec<RistrettoPoint> = share.G(16).cloned().collect();
+                    let H: Vec<RistrettoPoint> = share.H(16).cloned().collect();
+                    (G, H)
+                })
+            }).collect();
+
+        for (j, handle) in handles.into_iter().enumerate() {
+            let (G, H) = handle.join().unwrap();
+            assert_eq!(G, gens.share(j).G(16).cloned().collect::<Vec<_>>());
+            assert_eq!(H, gens.share(j).H(16).cloned().collect::<Vec<_>>());
+        }
+
+        assert_eq!(gens.owned_share(1).G(100).len(), 16);
+    }
+
+    #[test]
+    fn gens_iterators_are_exact_and_reversible() {
+        let gens = BulletproofGens::new(8, 4);
+
+        let expected: Vec<RistrettoPoint> = gens
+            .G_vec
+            .iter()
+            .take(3)
+            .flat_map(|G_j| G_j.iter().take(4))
+            .cloned()
+            .collect();
+
+        let mut iter = gens.G(4, 3);
+        assert_eq!(iter.len(), 12);
+        iter.next();
+        iter.next_back();
+        assert_eq!(iter.len(), 10);
+        let rest = iter.clone();
+        assert_eq!(iter.cloned().collect::<Vec<_>>(), expected[1..11].to_vec());
+        assert_eq!(rest.rev().cloned().collect::<Vec<_>>(), {
+            let mut mirrored = expected[1..11].to_vec();
+            mirrored.reverse();
+            mirrored
+        });
+
+        let mut iter = gens.H(4, 3);
+        while iter.next().is_some() {}
+        assert_eq!(iter.len(), 0);
+        assert!(iter.next().is_none());
+        assert!(iter.next_back().is_none());
+
+        let mut share_iter = gens.share(2).H(5);
+        assert_eq!(share_iter.len(), 5);
+        assert_eq!(share_iter.next_back(), Some(&gens.H_vec[2][4]));
+        assert_eq!(share_iter.len(), 4);
+        assert_eq!(gens.share(2).G(100).len(), 8);
+    }
 }
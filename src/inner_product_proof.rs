@@ -1,17 +1,39 @@
 #![allow(non_snake_case)]
 #![doc(include = "../docs/inner-product-protocol.md")]
 
-use std::borrow::Borrow;
-use std::iter;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+#[cfg(feature = "std")]
+use rand;
+use rand::{CryptoRng, RngCore};
+
+use core::borrow::Borrow;
+use core::iter;
 
 use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
 use curve25519_dalek::scalar::Scalar;
-use curve25519_dalek::traits::VartimeMultiscalarMul;
+use curve25519_dalek::traits::{IsIdentity, VartimeMultiscalarMul};
 use merlin::Transcript;
 
 use errors::ProofError;
 use transcript::TranscriptProtocol;
 
+/// An inner-product argument, proving knowledge of vectors
+/// \\(\mathbf{a}, \mathbf{b} \in {\mathbb Z\_p}^n\\) such that
+/// \\[
+/// P = {\langle \mathbf{a}, \mathbf{G} \rangle} + {\langle \mathbf{b}, \mathbf{H'} \rangle} + {\langle \mathbf{a}, \mathbf{b} \rangle} Q,
+/// \\]
+/// where \\(H'\_i = H\_i \cdot \texttt{Hprime\\_factors}\_i\\).
+///
+/// The proof does not bind the statement \\((P, Q, \mathbf{G}, \mathbf{H})\\)
+/// by itself: the caller is responsible for committing \\(P\\) (or
+/// whatever data determines it) and \\(Q\\) to the transcript before
+/// calling [`create`](InnerProductProof::create) or
+/// [`verify`](InnerProductProof::verify), and for choosing \\(Q\\) so
+/// that its discrete log relative to \\(\mathbf{G}, \mathbf{H}\\) is
+/// unknown to the prover.  The length \\(n\\) is committed by the
+/// proof itself.
 #[derive(Clone, Debug)]
 pub struct InnerProductProof {
     pub(crate) L_vec: Vec<CompressedRistretto>,
@@ -30,8 +52,11 @@ impl InnerProductProof {
     /// challenges depend on the *entire* transcript (including parent
     /// protocols).
     ///
-    /// The lengths of the vectors must all be the same, and must all be
-    /// either 0 or a power of 2.
+    /// The lengths of the vectors must all be the same and nonzero.
+    /// A proof for vectors of length \\(n\\) has
+    /// \\(\lceil \lg n \rceil\\) rounds; when \\(n\\) is not a
+    /// power of two, the vectors are not padded, but the last element
+    /// of a round with odd length is carried into the next round.
     pub fn create(
         transcript: &mut Transcript,
         Q: &RistrettoPoint,
@@ -56,48 +81,54 @@ impl InnerProductProof {
         assert_eq!(H.len(), n);
         assert_eq!(a.len(), n);
         assert_eq!(b.len(), n);
-
-        // All of the input vectors must have a length that is a power of two.
-        assert!(n.is_power_of_two());
+        assert!(n > 0);
 
         transcript.innerproduct_domain_sep(n as u64);
 
-        let lg_n = n.next_power_of_two().trailing_zeros() as usize;
+        let lg_n = num_rounds(n);
         let mut L_vec = Vec::with_capacity(lg_n);
         let mut R_vec = Vec::with_capacity(lg_n);
 
+        // Each round splits the vectors into a low half of length
+        // n_L = ceil(n/2) and a high half of length n_R = floor(n/2).
+        // If n is odd, the last element of the low half has no
+        // partner, and is only scaled when the halves are folded.
+
         // If it's the first iteration, unroll the Hprime = H*y_inv scalar mults
         // into multiscalar muls, for performance.
         if n != 1 {
-            n = n / 2;
-            let (a_L, a_R) = a.split_at_mut(n);
-            let (b_L, b_R) = b.split_at_mut(n);
-            let (G_L, G_R) = G.split_at_mut(n);
-            let (H_L, H_R) = H.split_at_mut(n);
+            let (n_L, n_R) = ((n + 1) / 2, n / 2);
+            n = n_L;
+            let (a_L, a_R) = a.split_at_mut(n_L);
+            let (b_L, b_R) = b.split_at_mut(n_L);
+            let (G_L, G_R) = G.split_at_mut(n_L);
+            let (H_L, H_R) = H.split_at_mut(n_L);
 
-            let c_L = inner_product(&a_L, &b_R);
-            let c_R = inner_product(&a_R, &b_L);
+            let c_L = inner_product(&a_L[..n_R], &b_R);
+            let c_R = inner_product(&a_R, &b_L[..n_R]);
 
             let L = RistrettoPoint::vartime_multiscalar_mul(
-                a_L.iter()
+                a_L[..n_R]
+                    .iter()
                     .cloned()
                     .chain(
                         b_R.iter()
-                            .zip(Hprime_factors[0..n].into_iter())
+                            .zip(Hprime_factors[0..n_R].into_iter())
                             .map(|(b_R_i, y_i)| b_R_i * y_i),
                     ).chain(iter::once(c_L)),
-                G_R.iter().chain(H_L.iter()).chain(iter::once(Q)),
+                G_R.iter().chain(H_L[..n_R].iter()).chain(iter::once(Q)),
             ).compress();
 
             let R = RistrettoPoint::vartime_multiscalar_mul(
                 a_R.iter()
                     .cloned()
                     .chain(
-                        b_L.iter()
-                            .zip(Hprime_factors[n..2 * n].into_iter())
+                        b_L[..n_R]
+                            .iter()
+                            .zip(Hprime_factors[n_L..n_L + n_R].into_iter())
                             .map(|(b_L_i, y_i)| b_L_i * y_i),
                     ).chain(iter::once(c_R)),
-                G_L.iter().chain(H_R.iter()).chain(iter::once(Q)),
+                G_L[..n_R].iter().chain(H_R.iter()).chain(iter::once(Q)),
             ).compress();
 
             L_vec.push(L);
@@ -109,15 +140,22 @@ impl InnerProductProof {
             let u = transcript.challenge_scalar(b"u");
             let u_inv = u.invert();
 
-            for i in 0..n {
+            for i in 0..n_R {
                 a_L[i] = a_L[i] * u + u_inv * a_R[i];
                 b_L[i] = b_L[i] * u_inv + u * b_R[i];
                 G_L[i] = RistrettoPoint::vartime_multiscalar_mul(&[u_inv, u], &[G_L[i], G_R[i]]);
                 H_L[i] = RistrettoPoint::vartime_multiscalar_mul(
-                    &[u * Hprime_factors[i], u_inv * Hprime_factors[n + i]],
+                    &[u * Hprime_factors[i], u_inv * Hprime_factors[n_L + i]],
                     &[H_L[i], H_R[i]],
                 )
             }
+            if n_L != n_R {
+                let i = n_R;
+                a_L[i] = a_L[i] * u;
+                b_L[i] = b_L[i] * u_inv;
+                G_L[i] = G_L[i] * u_inv;
+                H_L[i] = H_L[i] * (u * Hprime_factors[i]);
+            }
 
             a = a_L;
             b = b_L;
@@ -126,23 +164,27 @@ impl InnerProductProof {
         }
 
         while n != 1 {
-            n = n / 2;
-            let (a_L, a_R) = a.split_at_mut(n);
-            let (b_L, b_R) = b.split_at_mut(n);
-            let (G_L, G_R) = G.split_at_mut(n);
-            let (H_L, H_R) = H.split_at_mut(n);
+            let (n_L, n_R) = ((n + 1) / 2, n / 2);
+            n = n_L;
+            let (a_L, a_R) = a.split_at_mut(n_L);
+            let (b_L, b_R) = b.split_at_mut(n_L);
+            let (G_L, G_R) = G.split_at_mut(n_L);
+            let (H_L, H_R) = H.split_at_mut(n_L);
 
-            let c_L = inner_product(&a_L, &b_R);
-            let c_R = inner_product(&a_R, &b_L);
+            let c_L = inner_product(&a_L[..n_R], &b_R);
+            let c_R = inner_product(&a_R, &b_L[..n_R]);
 
             let L = RistrettoPoint::vartime_multiscalar_mul(
-                a_L.iter().chain(b_R.iter()).chain(iter::once(&c_L)),
-                G_R.iter().chain(H_L.iter()).chain(iter::once(Q)),
+                a_L[..n_R]
+                    .iter()
+                    .chain(b_R.iter())
+                    .chain(iter::once(&c_L)),
+                G_R.iter().chain(H_L[..n_R].iter()).chain(iter::once(Q)),
             ).compress();
 
             let R = RistrettoPoint::vartime_multiscalar_mul(
-                a_R.iter().chain(b_L.iter()).chain(iter::once(&c_R)),
-                G_L.iter().chain(H_R.iter()).chain(iter::once(Q)),
+                a_R.iter().chain(b_L[..n_R].iter()).chain(iter::once(&c_R)),
+                G_L[..n_R].iter().chain(H_R.iter()).chain(iter::once(Q)),
             ).compress();
 
             L_vec.push(L);
@@ -154,12 +196,19 @@ impl InnerProductProof {
             let u = transcript.challenge_scalar(b"u");
             let u_inv = u.invert();
 
-            for i in 0..n {
+            for i in 0..n_R {
                 a_L[i] = a_L[i] * u + u_inv * a_R[i];
                 b_L[i] = b_L[i] * u_inv + u * b_R[i];
                 G_L[i] = RistrettoPoint::vartime_multiscalar_mul(&[u_inv, u], &[G_L[i], G_R[i]]);
                 H_L[i] = RistrettoPoint::vartime_multiscalar_mul(&[u, u_inv], &[H_L[i], H_R[i]]);
             }
+            if n_L != n_R {
+                let i = n_R;
+                a_L[i] = a_L[i] * u;
+                b_L[i] = b_L[i] * u_inv;
+                G_L[i] = G_L[i] * u_inv;
+                H_L[i] = H_L[i] * u;
+            }
 
             a = a_L;
             b = b_L;
@@ -177,12 +226,41 @@ impl InnerProductProof {
 
     /// Computes three vectors of verification scalars \\([u\_{i}^{2}]\\), \\([u\_{i}^{-2}]\\) and \\([s\_{i}]\\) for combined multiscalar multiplication
     /// in a parent protocol. See [inner product protocol notes](index.html#verification-equation) for details.
-    pub(crate) fn verification_scalars(
+    ///
+    /// The challenges \\(u\_i\\) are returned in the order they are
+    /// drawn from the `transcript`, which must have the same state as
+    /// the one passed to [`create`](InnerProductProof::create).  The
+    /// proof is valid if
+    /// \\[
+    /// P = \langle a \cdot \mathbf{s}, \mathbf{G} \rangle
+    ///   + \langle b / \mathbf{s}, \mathbf{H'} \rangle
+    ///   + ab \cdot Q
+    ///   - \sum\_{i} u\_i^2 L\_i - \sum\_{i} u\_i^{-2} R\_i,
+    /// \\]
+    /// where \\(1/s\_i\\) is \\(s\_{n-1-i}\\) if \\(n\\) is a power of
+    /// two.
+    /// The points \\(L\_i, R\_i\\) and the scalars \\(a, b\\) can be
+    /// read from the encoding returned by
+    /// [`to_bytes`](InnerProductProof::to_bytes).
+    ///
+    /// Returns [`ProofError::WrongNumRounds`] if the proof does not
+    /// have \\(\lceil \lg n \rceil\\) rounds for vectors of length
+    /// `n`.
+    pub fn verification_scalars(
         &self,
+        n: usize,
         transcript: &mut Transcript,
-    ) -> (Vec<Scalar>, Vec<Scalar>, Vec<Scalar>) {
+    ) -> Result<(Vec<Scalar>, Vec<Scalar>, Vec<Scalar>), ProofError> {
         let lg_n = self.L_vec.len();
-        let n = 1 << lg_n;
+        if n == 0 || self.R_vec.len() != lg_n {
+            return Err(ProofError::FormatError);
+        }
+        if num_rounds(n) != lg_n {
+            return Err(ProofError::WrongNumRounds {
+                expected: num_rounds(n),
+                actual: lg_n,
+            });
+        }
 
         transcript.innerproduct_domain_sep(n as u64);
 
@@ -210,27 +288,43 @@ impl InnerProductProof {
         let challenges_sq = challenges;
         let challenges_inv_sq = challenges_inv;
 
-        // 4. Compute s values inductively.
+        // 4. Compute s values inductively, undoing the rounds from
+        // the last to the first.  A round of length m folds the high
+        // half onto the first floor(m/2) elements of the low half, so
+        // those s values extend s by themselves times u^2.  The
+        // challenges are stored in "creation order" as [u_k,...,u_1].
+
+        let mut round_lengths = Vec::with_capacity(lg_n);
+        let mut m = n;
+        while m > 1 {
+            round_lengths.push(m);
+            m = (m + 1) / 2;
+        }
 
         let mut s = Vec::with_capacity(n);
         s.push(allinv);
-        for i in 1..n {
-            let lg_i = (32 - 1 - (i as u32).leading_zeros()) as usize;
-            let k = 1 << lg_i;
-            // The challenges are stored in "creation order" as [u_k,...,u_1],
-            // so u_{lg(i)+1} = is indexed by (lg_n-1) - lg_i
-            let u_lg_i_sq = challenges_sq[(lg_n - 1) - lg_i];
-            s.push(s[i - k] * u_lg_i_sq);
+        for (m, u_sq) in round_lengths.iter().zip(challenges_sq.iter()).rev() {
+            for k in 0..m / 2 {
+                let s_k = s[k] * u_sq;
+                s.push(s_k);
+            }
         }
 
-        (challenges_sq, challenges_inv_sq, s)
+        Ok((challenges_sq, challenges_inv_sq, s))
     }
 
-    /// This method is for testing that proof generation work,
-    /// but for efficiency the actual protocols would use `verification_scalars`
-    /// method to combine inner product verification with other checks
-    /// in a single multiscalar multiplication.
-    #[allow(dead_code)]
+    /// Verifies an inner-product proof for the point `P` with
+    /// respect to the bases `G`, `H'` and `Q`, where \\(H'\_i = H\_i
+    /// \cdot \texttt{Hprime\\_factors}\_i\\).
+    ///
+    /// The `transcript` must have the same state as the one passed
+    /// to [`create`](InnerProductProof::create).  The lengths of `G`
+    /// and `H` must equal the length of the proven vectors.
+    ///
+    /// Protocols embedding the inner-product argument should instead
+    /// use [`verification_scalars`](InnerProductProof::verification_scalars)
+    /// to combine this check with their own in a single multiscalar
+    /// multiplication.
     pub fn verify<I>(
         &self,
         transcript: &mut Transcript,
@@ -244,16 +338,23 @@ impl InnerProductProof {
         I: IntoIterator,
         I::Item: Borrow<Scalar>,
     {
-        let (u_sq, u_inv_sq, s) = self.verification_scalars(transcript);
+        let n = G.len();
+        if H.len() != n {
+            return Err(ProofError::InvalidGeneratorsLength {
+                expected: n,
+                actual: H.len(),
+            });
+        }
+
+        let (u_sq, u_inv_sq, s) = self.verification_scalars(n, transcript)?;
 
         let a_times_s = s.iter().map(|s_i| self.a * s_i);
 
-        // 1/s[i] is s[!i], and !i runs from n-1 to 0 as i runs from 0 to n-1
-        let inv_s = s.iter().rev();
+        let inv_s = invert_scalars(&s);
 
         let h_times_b_div_s = Hprime_factors
             .into_iter()
-            .zip(inv_s)
+            .zip(inv_s.iter())
             .map(|(h_i, s_i_inv)| (self.b * s_i_inv) * h_i.borrow());
 
         let neg_u_sq = u_sq.iter().map(|ui| -ui);
@@ -262,14 +363,18 @@ impl InnerProductProof {
         let Ls = self
             .L_vec
             .iter()
-            .map(|p| p.decompress().ok_or(ProofError::VerificationError))
-            .collect::<Result<Vec<_>, _>>()?;
+            .map(|p| {
+                p.decompress()
+                    .ok_or(ProofError::PointDecompressionError { which: "L" })
+            }).collect::<Result<Vec<_>, _>>()?;
 
         let Rs = self
             .R_vec
             .iter()
-            .map(|p| p.decompress().ok_or(ProofError::VerificationError))
-            .collect::<Result<Vec<_>, _>>()?;
+            .map(|p| {
+                p.decompress()
+                    .ok_or(ProofError::PointDecompressionError { which: "R" })
+            }).collect::<Result<Vec<_>, _>>()?;
 
         let expect_P = RistrettoPoint::vartime_multiscalar_mul(
             iter::once(self.a * self.b)
@@ -291,18 +396,179 @@ impl InnerProductProof {
         }
     }
 
+    /// Verifies a batch of inner-product proofs, where the `k`-th
+    /// proof is checked against `transcripts[k]`,
+    /// `Hprime_factors[k]` and `P[k]`, and all proofs share the
+    /// bases `Q`, `G` and `H`.
+    ///
+    /// This is the same as calling
+    /// [`verify`](InnerProductProof::verify) on each proof, but the
+    /// verification equations are combined with random weights and
+    /// checked with a single multiscalar multiplication.
+    #[cfg(feature = "std")]
+    pub fn verify_batch(
+        proofs: &[InnerProductProof],
+        transcripts: &mut [Transcript],
+        Hprime_factors: &[&[Scalar]],
+        P: &[RistrettoPoint],
+        Q: &RistrettoPoint,
+        G: &[RistrettoPoint],
+        H: &[RistrettoPoint],
+    ) -> Result<(), ProofError> {
+        InnerProductProof::verify_batch_with_rng(
+            proofs,
+            transcripts,
+            Hprime_factors,
+            P,
+            Q,
+            G,
+            H,
+            &mut rand::thread_rng(),
+        )
+    }
+
+    /// Verifies a batch of inner-product proofs, drawing the
+    /// verifier's randomness from `rng`.
+    ///
+    /// This is the same as
+    /// [`verify_batch`](InnerProductProof::verify_batch), but is also
+    /// available without the `std` feature.
+    ///
+    /// The length of the `k`-th proof is the length of
+    /// `Hprime_factors[k]`.  Returns [`ProofError::FormatError`] if
+    /// the number of transcripts, factor vectors or points differs
+    /// from the number of proofs, or if a proof has the wrong number
+    /// of rounds for its length, and
+    /// [`ProofError::InvalidGeneratorsLength`] if `G` or `H` is
+    /// shorter than some proof.
+    pub fn verify_batch_with_rng<T: RngCore + CryptoRng>(
+        proofs: &[InnerProductProof],
+        transcripts: &mut [Transcript],
+        Hprime_factors: &[&[Scalar]],
+        P: &[RistrettoPoint],
+        Q: &RistrettoPoint,
+        G: &[RistrettoPoint],
+        H: &[RistrettoPoint],
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        let batch_size = proofs.len();
+        if transcripts.len() != batch_size
+            || Hprime_factors.len() != batch_size
+            || P.len() != batch_size
+        {
+            return Err(ProofError::FormatError);
+        }
+
+        let mut max_n = 0;
+        for factors in Hprime_factors.iter() {
+            let n = factors.len();
+            let shortest = G.len().min(H.len());
+            if shortest < n {
+                return Err(ProofError::InvalidGeneratorsLength {
+                    expected: n,
+                    actual: shortest,
+                });
+            }
+            max_n = max_n.max(n);
+        }
+
+        // Scalars for Q, G and H accumulate over all proofs, while
+        // each proof contributes its own L_i, R_i and P.
+        let mut Q_scalar = Scalar::zero();
+        let mut G_scalars = vec![Scalar::zero(); max_n];
+        let mut H_scalars = vec![Scalar::zero(); max_n];
+        let mut dynamic_scalars = Vec::new();
+        let mut dynamic_points = Vec::new();
+
+        for (k, proof) in proofs.iter().enumerate() {
+            let n = Hprime_factors[k].len();
+            let transcript = &mut transcripts[k];
+            let (u_sq, u_inv_sq, s) = proof.verification_scalars(n, transcript)?;
+            let s_inv = invert_scalars(&s);
+
+            // Weight for this proof's verification equation
+            let mut rng = transcript.build_rng().finalize(rng);
+            let c = Scalar::random(&mut rng);
+
+            let ca = c * proof.a;
+            let cb = c * proof.b;
+
+            Q_scalar += ca * proof.b;
+            for i in 0..n {
+                G_scalars[i] += ca * s[i];
+                H_scalars[i] += cb * s_inv[i] * Hprime_factors[k][i];
+            }
+
+            for (L, u_sq_i) in proof.L_vec.iter().zip(u_sq.iter()) {
+                let L = L
+                    .decompress()
+                    .ok_or(ProofError::PointDecompressionError { which: "L" })?;
+                dynamic_scalars.push(-c * u_sq_i);
+                dynamic_points.push(L);
+            }
+            for (R, u_inv_sq_i) in proof.R_vec.iter().zip(u_inv_sq.iter()) {
+                let R = R
+                    .decompress()
+                    .ok_or(ProofError::PointDecompressionError { which: "R" })?;
+                dynamic_scalars.push(-c * u_inv_sq_i);
+                dynamic_points.push(R);
+            }
+            dynamic_scalars.push(-c);
+            dynamic_points.push(P[k]);
+        }
+
+        let check = RistrettoPoint::vartime_multiscalar_mul(
+            iter::once(Q_scalar)
+                .chain(G_scalars)
+                .chain(H_scalars)
+                .chain(dynamic_scalars),
+            iter::once(Q)
+                .chain(G.iter().take(max_n))
+                .chain(H.iter().take(max_n))
+                .chain(dynamic_points.iter()),
+        );
+
+        if check.is_identity() {
+            Ok(())
+        } else {
+            Err(ProofError::VerificationError)
+        }
+    }
+
+    /// The points \\(L_0, \ldots, L_{k-1}\\), one for each round.
+    pub fn L_vec(&self) -> &[CompressedRistretto] {
+        &self.L_vec
+    }
+
+    /// The points \\(R_0, \ldots, R_{k-1}\\), one for each round.
+    pub fn R_vec(&self) -> &[CompressedRistretto] {
+        &self.R_vec
+    }
+
+    /// The final scalar \\(a\\).
+    pub fn a(&self) -> Scalar {
+        self.a
+    }
+
+    /// The final scalar \\(b\\).
+    pub fn b(&self) -> Scalar {
+        self.b
+    }
+
     /// Returns the size in bytes required to serialize the inner
     /// product proof.
     ///
-    /// For vectors of length `n` the proof size is
-    /// \\(32 \cdot (2\lg n+2)\\) bytes.
+    /// For vectors of length `n` the proof has \\(k = \lceil \lg n \rceil\\)
+    /// rounds, and its size is \\(32 \cdot (2k+2)\\) bytes.
     pub fn serialized_size(&self) -> usize {
         (self.L_vec.len() * 2 + 2) * 32
     }
 
-    /// Serializes the proof into a byte array of \\(2n+2\\) 32-byte elements.
+    /// Serializes the proof into a byte array of \\(2k+2\\) 32-byte
+    /// elements, where \\(k = \lceil \lg n \rceil\\) is the number of
+    /// rounds for vectors of length \\(n\\).
     /// The layout of the inner product proof is:
-    /// * \\(n\\) pairs of compressed Ristretto points \\(L_0, R_0 \dots, L_{n-1}, R_{n-1}\\),
+    /// * \\(k\\) pairs of compressed Ristretto points \\(L_0, R_0 \dots, L_{k-1}, R_{k-1}\\),
     /// * two scalars \\(a, b\\).
     pub fn to_bytes(&self) -> Vec<u8> {
         let mut buf = Vec::with_capacity(self.serialized_size());
@@ -317,26 +583,17 @@ impl InnerProductProof {
 
     /// Deserializes the proof from a byte slice.
     /// Returns an error in the following cases:
-    /// * the slice does not have \\(2n+2\\) 32-byte elements,
-    /// * \\(n\\) is larger or equal to 32 (proof is too big),
-    /// * any of \\(2n\\) points are not valid compressed Ristretto points,
-    /// * any of 2 scalars are not canonical scalars modulo Ristretto group order.
+    /// * the slice does not have \\(2k+2\\) 32-byte elements for
+    ///   some number of rounds \\(k\\)
+    ///   ([`ProofError::WrongProofLength`]),
+    /// * \\(k\\) is larger or equal to 32, so the proof is too big
+    ///   ([`ProofError::WrongNumRounds`]),
+    /// * any of 2 scalars are not canonical scalars modulo Ristretto
+    ///   group order ([`ProofError::FormatError`]).
+    ///
+    /// The \\(2k\\) points are decompressed during verification.
     pub fn from_bytes(slice: &[u8]) -> Result<InnerProductProof, ProofError> {
-        let b = slice.len();
-        if b % 32 != 0 {
-            return Err(ProofError::FormatError);
-        }
-        let num_elements = b / 32;
-        if num_elements < 2 {
-            return Err(ProofError::FormatError);
-        }
-        if (num_elements - 2) % 2 != 0 {
-            return Err(ProofError::FormatError);
-        }
-        let lg_n = (num_elements - 2) / 2;
-        if lg_n >= 32 {
-            return Err(ProofError::FormatError);
-        }
+        let lg_n = parse_num_rounds(slice.len(), 2)?;
 
         use util::read32;
 
@@ -358,6 +615,53 @@ impl InnerProductProof {
     }
 }
 
+/// Returns the number of rounds \\(\lceil \lg n \rceil\\) of an
+/// inner-product proof for vectors of length `n`.
+fn num_rounds(n: usize) -> usize {
+    let mut rounds = 0;
+    let mut m = n;
+    while m > 1 {
+        m = (m + 1) / 2;
+        rounds += 1;
+    }
+    rounds
+}
+
+/// Returns the number of rounds \\(k\\) of a proof encoded in `len`
+/// bytes, which must be \\(2k + c\\) 32-byte elements, for the
+/// \\(2k\\) points of the rounds and `c` further elements.
+pub(crate) fn parse_num_rounds(len: usize, c: usize) -> Result<usize, ProofError> {
+    let num_elements = len / 32;
+    if len % 32 != 0 || num_elements < c || (num_elements - c) % 2 != 0 {
+        // Report the shortest valid length which is at least `len`
+        let k = (((len + 31) / 32).saturating_sub(c) + 1) / 2;
+        return Err(ProofError::WrongProofLength {
+            expected: (2 * k + c) * 32,
+            actual: len,
+        });
+    }
+    let lg_n = (num_elements - c) / 2;
+    if lg_n >= 32 {
+        return Err(ProofError::WrongNumRounds {
+            expected: 31,
+            actual: lg_n,
+        });
+    }
+    Ok(lg_n)
+}
+
+/// Returns the inverses of the verification scalars `s`.
+fn invert_scalars(s: &[Scalar]) -> Vec<Scalar> {
+    // 1/s[i] is s[!i] when the length is a power of two
+    if s.len().is_power_of_two() {
+        s.iter().rev().cloned().collect()
+    } else {
+        let mut s_inv = s.to_vec();
+        Scalar::batch_invert(&mut s_inv);
+        s_inv
+    }
+}
+
 /// Computes an inner product of two vectors
 /// \\[
 ///    {\langle {\mathbf{a}}, {\mathbf{b}} \rangle} = \sum\_{i=0}^{n-1} a\_i \cdot b\_i.
@@ -378,12 +682,11 @@ pub fn inner_product(a: &[Scalar], b: &[Scalar]) -> Scalar {
 mod tests {
     use super::*;
 
-    use rand::OsRng;
     use sha3::Sha3_512;
     use util;
 
     fn test_helper_create(n: usize) {
-        let mut rng = OsRng::new().unwrap();
+        let mut rng = util::deterministic_rng([70u8; 32]);
 
         use generators::BulletproofGens;
         let bp_gens = BulletproofGens::new(n, 1);
@@ -427,6 +730,8 @@ mod tests {
             b.clone(),
         );
 
+        assert_eq!(proof.L_vec.len(), num_rounds(n));
+
         let mut verifier = Transcript::new(b"innerproducttest");
         assert!(
             proof
@@ -441,6 +746,39 @@ mod tests {
                 .verify(&mut verifier, util::exp_iter(y_inv), &P, &Q, &G, &H)
                 .is_ok()
         );
+
+        // Reconstruct the verification from the public scalars
+        let mut verifier = Transcript::new(b"innerproducttest");
+        let (u_sq, u_inv_sq, s) = proof.verification_scalars(n, &mut verifier).unwrap();
+        let Ls: Vec<_> = proof.L_vec.iter().map(|L| L.decompress().unwrap()).collect();
+        let Rs: Vec<_> = proof.R_vec.iter().map(|R| R.decompress().unwrap()).collect();
+        let expect_P = RistrettoPoint::vartime_multiscalar_mul(
+            iter::once(proof.a * proof.b)
+                .chain(s.iter().map(|s_i| proof.a * s_i))
+                .chain(
+                    invert_scalars(&s)
+                        .iter()
+                        .zip(util::exp_iter(y_inv))
+                        .map(|(s_i_inv, h_i)| proof.b * s_i_inv * h_i),
+                ).chain(u_sq.iter().map(|u| -u))
+                .chain(u_inv_sq.iter().map(|u| -u)),
+            iter::once(&Q)
+                .chain(G.iter())
+                .chain(H.iter())
+                .chain(Ls.iter())
+                .chain(Rs.iter()),
+        );
+        assert_eq!(expect_P, P);
+
+        // The number of rounds must match n
+        let mut verifier = Transcript::new(b"innerproducttest");
+        assert_eq!(
+            proof.verification_scalars(2 * n, &mut verifier).unwrap_err(),
+            ProofError::WrongNumRounds {
+                expected: num_rounds(2 * n),
+                actual: num_rounds(n)
+            }
+        );
     }
 
     #[test]
@@ -453,11 +791,21 @@ mod tests {
         test_helper_create(2);
     }
 
+    #[test]
+    fn make_ipp_3() {
+        test_helper_create(3);
+    }
+
     #[test]
     fn make_ipp_4() {
         test_helper_create(4);
     }
 
+    #[test]
+    fn make_ipp_5() {
+        test_helper_create(5);
+    }
+
     #[test]
     fn make_ipp_32() {
         test_helper_create(32);
@@ -468,6 +816,101 @@ mod tests {
         test_helper_create(64);
     }
 
+    #[test]
+    fn make_ipp_1025() {
+        test_helper_create(1025);
+    }
+
+    #[test]
+    fn odd_length_rounds() {
+        assert_eq!(num_rounds(1), 0);
+        assert_eq!(num_rounds(3), 2);
+        assert_eq!(num_rounds(5), 3);
+        assert_eq!(num_rounds(8), 3);
+        assert_eq!(num_rounds(1025), 11);
+    }
+
+    #[test]
+    fn odd_length_proof_is_bound_to_length() {
+        let mut rng = util::deterministic_rng([71u8; 32]);
+        let n = 5;
+
+        use generators::BulletproofGens;
+        let bp_gens = BulletproofGens::new(8, 1);
+        let G: Vec<RistrettoPoint> = bp_gens.share(0).G(8).cloned().collect();
+        let H: Vec<RistrettoPoint> = bp_gens.share(0).H(8).cloned().collect();
+        let Q = RistrettoPoint::hash_from_bytes::<Sha3_512>(b"test point");
+
+        let a: Vec<_> = (0..n).map(|_| Scalar::random(&mut rng)).collect();
+        let b: Vec<_> = (0..n).map(|_| Scalar::random(&mut rng)).collect();
+        let c = inner_product(&a, &b);
+        let Hprime_factors = vec![Scalar::one(); 8];
+
+        // Padding with zeros gives the same statement for every length from 5 to 8.
+        let P = RistrettoPoint::vartime_multiscalar_mul(
+            a.iter().chain(b.iter()).chain(iter::once(&c)),
+            G[..n].iter().chain(H[..n].iter()).chain(iter::once(&Q)),
+        );
+
+        let mut transcript = Transcript::new(b"innerproducttest");
+        let proof = InnerProductProof::create(
+            &mut transcript,
+            &Q,
+            &Hprime_factors[..n],
+            G[..n].to_vec(),
+            H[..n].to_vec(),
+            a,
+            b,
+        );
+        assert_eq!(proof.L_vec.len(), 3);
+
+        let mut transcript = Transcript::new(b"innerproducttest");
+        assert!(
+            proof
+                .verify(
+                    &mut transcript,
+                    &Hprime_factors[..n],
+                    &P,
+                    &Q,
+                    &G[..n],
+                    &H[..n]
+                ).is_ok()
+        );
+
+        // The same number of rounds, but a different length, must not verify.
+        for m in 6..9 {
+            let mut transcript = Transcript::new(b"innerproducttest");
+            assert_eq!(
+                proof.verify(
+                    &mut transcript,
+                    &Hprime_factors[..m],
+                    &P,
+                    &Q,
+                    &G[..m],
+                    &H[..m]
+                ),
+                Err(ProofError::VerificationError)
+            );
+        }
+
+        // A proof of 3 rounds doesn't verify for a statement of 2.
+        let mut transcript = Transcript::new(b"innerproducttest");
+        assert_eq!(
+            proof.verify(
+                &mut transcript,
+                &Hprime_factors[..4],
+                &P,
+                &Q,
+                &G[..4],
+                &H[..4]
+            ),
+            Err(ProofError::WrongNumRounds {
+                expected: 2,
+                actual: 3
+            })
+        );
+    }
+
     #[test]
     fn test_inner_product() {
         let a = vec![
@@ -484,4 +927,187 @@ mod tests {
         ];
         assert_eq!(Scalar::from(40u64), inner_product(&a, &b));
     }
+
+    #[test]
+    fn corrupted_proof_is_rejected() {
+        let mut rng = util::deterministic_rng([72u8; 32]);
+        let n = 32;
+
+        use generators::BulletproofGens;
+        let bp_gens = BulletproofGens::new(n, 1);
+        let G: Vec<RistrettoPoint> = bp_gens.share(0).G(n).cloned().collect();
+        let H: Vec<RistrettoPoint> = bp_gens.share(0).H(n).cloned().collect();
+        let Q = RistrettoPoint::hash_from_bytes::<Sha3_512>(b"test point");
+
+        let a: Vec<_> = (0..n).map(|_| Scalar::random(&mut rng)).collect();
+        let b: Vec<_> = (0..n).map(|_| Scalar::random(&mut rng)).collect();
+        let c = inner_product(&a, &b);
+        let Hprime_factors = vec![Scalar::one(); n];
+
+        let P = RistrettoPoint::vartime_multiscalar_mul(
+            a.iter().chain(b.iter()).chain(iter::once(&c)),
+            G.iter().chain(H.iter()).chain(iter::once(&Q)),
+        );
+
+        let mut transcript = Transcript::new(b"innerproducttest");
+        let proof = InnerProductProof::create(
+            &mut transcript,
+            &Q,
+            &Hprime_factors,
+            G.clone(),
+            H.clone(),
+            a,
+            b,
+        );
+
+        // Flip a bit in the final scalar `a`.
+        let mut bytes = proof.to_bytes();
+        let pos = bytes.len() - 64;
+        bytes[pos] ^= 1;
+        let bad_proof = InnerProductProof::from_bytes(&bytes).unwrap();
+
+        let mut transcript = Transcript::new(b"innerproducttest");
+        assert!(
+            bad_proof
+                .verify(&mut transcript, &Hprime_factors, &P, &Q, &G, &H)
+                .is_err()
+        );
+
+        // A truncated encoding fails to parse: the proof is 2*5 + 2 elements.
+        assert_eq!(
+            InnerProductProof::from_bytes(&bytes[..bytes.len() - 32]).unwrap_err(),
+            ProofError::WrongProofLength {
+                expected: 12 * 32,
+                actual: 11 * 32
+            }
+        );
+        assert_eq!(
+            InnerProductProof::from_bytes(&bytes[..33]).unwrap_err(),
+            ProofError::WrongProofLength {
+                expected: 2 * 32,
+                actual: 33
+            }
+        );
+        assert_eq!(
+            InnerProductProof::from_bytes(&vec![0u8; (2 * 32 + 2) * 32]).unwrap_err(),
+            ProofError::WrongNumRounds {
+                expected: 31,
+                actual: 32
+            }
+        );
+
+        // Generators of the wrong length are rejected.
+        let mut transcript = Transcript::new(b"innerproducttest");
+        assert!(
+            proof
+                .verify(&mut transcript, &Hprime_factors, &P, &Q, &G[..16], &H[..16])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn batch_verification() {
+        let mut rng = util::deterministic_rng([73u8; 32]);
+        let max_n = 16;
+
+        use generators::BulletproofGens;
+        let bp_gens = BulletproofGens::new(max_n, 1);
+        let G: Vec<RistrettoPoint> = bp_gens.share(0).G(max_n).cloned().collect();
+        let H: Vec<RistrettoPoint> = bp_gens.share(0).H(max_n).cloned().collect();
+        let Q = RistrettoPoint::hash_from_bytes::<Sha3_512>(b"test point");
+
+        // Proofs of different lengths share prefixes of the same bases.
+        let sizes = [1, 4, 16, 8, 5, 11];
+        let mut proofs = Vec::new();
+        let mut factors = Vec::new();
+        let mut Ps = Vec::new();
+        for &n in sizes.iter() {
+            let a: Vec<_> = (0..n).map(|_| Scalar::random(&mut rng)).collect();
+            let b: Vec<_> = (0..n).map(|_| Scalar::random(&mut rng)).collect();
+            let c = inner_product(&a, &b);
+            let y_inv = Scalar::random(&mut rng);
+            let Hprime_factors: Vec<Scalar> = util::exp_iter(y_inv).take(n).collect();
+
+            let b_prime = b.iter().zip(Hprime_factors.iter()).map(|(bi, yi)| bi * yi);
+            Ps.push(RistrettoPoint::vartime_multiscalar_mul(
+                a.iter().cloned().chain(b_prime).chain(iter::once(c)),
+                G[..n].iter().chain(H[..n].iter()).chain(iter::once(&Q)),
+            ));
+
+            let mut transcript = Transcript::new(b"innerproducttest");
+            proofs.push(InnerProductProof::create(
+                &mut transcript,
+                &Q,
+                &Hprime_factors,
+                G[..n].to_vec(),
+                H[..n].to_vec(),
+                a,
+                b,
+            ));
+            factors.push(Hprime_factors);
+        }
+        let factors: Vec<&[Scalar]> = factors.iter().map(|f| f.as_slice()).collect();
+        let transcripts = || vec![Transcript::new(b"innerproducttest"); sizes.len()];
+
+        assert!(
+            InnerProductProof::verify_batch_with_rng(
+                &proofs,
+                &mut transcripts(),
+                &factors,
+                &Ps,
+                &Q,
+                &G,
+                &H,
+                &mut rng
+            ).is_ok()
+        );
+
+        // A single bad statement makes the whole batch fail.
+        let mut bad_Ps = Ps.clone();
+        bad_Ps[2] += Q;
+        assert_eq!(
+            InnerProductProof::verify_batch_with_rng(
+                &proofs,
+                &mut transcripts(),
+                &factors,
+                &bad_Ps,
+                &Q,
+                &G,
+                &H,
+                &mut rng
+            ),
+            Err(ProofError::VerificationError)
+        );
+
+        // Mismatched batch inputs are rejected.
+        assert_eq!(
+            InnerProductProof::verify_batch_with_rng(
+                &proofs,
+                &mut transcripts(),
+                &factors,
+                &Ps[..3],
+                &Q,
+                &G,
+                &H,
+                &mut rng
+            ),
+            Err(ProofError::FormatError)
+        );
+        assert_eq!(
+            InnerProductProof::verify_batch_with_rng(
+                &proofs,
+                &mut transcripts(),
+                &factors,
+                &Ps,
+                &Q,
+                &G[..8],
+                &H[..8],
+                &mut rng
+            ),
+            Err(ProofError::InvalidGeneratorsLength {
+                expected: 16,
+                actual: 8
+            })
+        );
+    }
 }
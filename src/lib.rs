@@ -1,11 +1,22 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+#![cfg_attr(not(feature = "std"), feature(alloc))]
 #![feature(nll)]
 #![feature(external_doc)]
 #![deny(missing_docs)]
 #![doc(include = "../README.md")]
 #![doc(html_logo_url = "https://doc.dalek.rs/assets/dalek-logo-clear.png")]
 
-extern crate byteorder;
+#[cfg(not(feature = "std"))]
+#[macro_use]
+extern crate alloc;
+#[cfg(feature = "std")]
 extern crate core;
+// The tests use threads, so they link std even when the crate doesn't.
+#[cfg(all(test, not(feature = "std")))]
+extern crate std;
+
+extern crate byteorder;
+extern crate clear_on_drop;
 extern crate digest;
 extern crate rand;
 extern crate sha3;
@@ -18,11 +29,17 @@ extern crate subtle;
 extern crate serde_derive;
 extern crate serde;
 
-#[macro_use]
-extern crate failure;
+#[cfg(feature = "wasm")]
+extern crate wasm_bindgen;
 
-#[cfg(test)]
+#[cfg(any(test, feature = "mac-messages"))]
 extern crate bincode;
+#[cfg(feature = "mac-messages")]
+extern crate hmac;
+#[cfg(feature = "mac-messages")]
+extern crate sha2;
+#[cfg(feature = "parallel")]
+extern crate rayon;
 
 mod util;
 
@@ -34,9 +51,17 @@ mod inner_product_proof;
 mod range_proof;
 mod transcript;
 
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
 pub use errors::ProofError;
-pub use generators::{BulletproofGens, BulletproofGensShare, PedersenGens};
+pub use generators::{
+    AggregatedGensIter, BulletproofGens, BulletproofGensShare, OwnedBulletproofGensShare,
+    PedersenGens,
+};
+pub use inner_product_proof::InnerProductProof;
 pub use range_proof::RangeProof;
+pub use transcript::TranscriptProtocol;
 
 #[doc(include = "../docs/aggregation-api.md")]
 pub mod aggregation {
@@ -44,4 +69,7 @@ pub mod aggregation {
     pub use range_proof::dealer;
     pub use range_proof::messages;
     pub use range_proof::party;
+    #[cfg(feature = "std")]
+    pub use range_proof::aggregate_locally;
+    pub use range_proof::aggregate_locally_with_rng;
 }
@@ -1,22 +1,94 @@
 //! The `dealer` module contains the API for the dealer state while the dealer is
 //! engaging in an aggregated multiparty computation protocol.
 //!
+//! A dealer which needs to wait for the parties outside of memory
+//! can `save` its state into a serializable form, and `resume` it
+//! later given the public parameters and a transcript in the same
+//! initial state as the one the dealer was created with.  The
+//! dealer's transcript is not saved: resuming replays the protocol
+//! messages received so far onto the given transcript.
+//!
+//! # Party dropout
+//!
+//! If a party stops responding, the dealer can call `abort_party` to
+//! restart the protocol without it, or `abort_parties` to restart
+//! without several parties at once.  The restarted aggregation is a
+//! [mixed-bitsize aggregation](Dealer::new_with_bitsizes) of the
+//! remaining parties, in their original order, so the positions are
+//! compacted: a party takes the position given by the number of
+//! remaining parties before it.  The remaining parties must start over
+//! from [`Party::new`](::aggregation::party::Party::new) with their
+//! same values and blinding factors, discarding their old states.
+//! Since they reuse their blinding factors, their value commitments
+//! are unchanged.
+//!
+//! In the last round, a dealer which gives up waiting for some proof
+//! shares can pass the shares it has to
+//! [`collect_shares`](DealerAwaitingProofShares::collect_shares),
+//! which reports the missing positions with
+//! [`MPCError::PartyDropout`] so that they can be aborted.
+//!
+//! Restarting is safe as long as the parties discard their old
+//! states: each party's blinding factors are only ever combined
+//! with the challenges of a single run, and a run which is aborted
+//! before the parties send their proof shares reveals nothing but
+//! hiding commitments.  Note that the restarted proof does not
+//! include the dropped party's value, so the application must decide
+//! whether a proof for a subset of the parties is acceptable, and
+//! that a malicious party can prevent completion by repeatedly
+//! dropping out.
+//!
+//! # Streaming
+//!
+//! For large aggregations, the dealer can switch to a streaming mode
+//! with [`DealerAwaitingBitCommitments::streaming`], where it absorbs
+//! each party's message as it arrives, instead of being handed all the
+//! messages of a round at once.  Messages must be absorbed in the
+//! order of the parties' positions.  The streaming dealer checks each
+//! proof share as it is absorbed, and moves the share's vectors into
+//! the inner-product proof's inputs without copying them.
+//!
+//! Streaming does not make the dealer's memory use independent of the
+//! number of parties \\(m\\).  The dealer keeps each party's bit and
+//! poly commitments, which are needed to check its proof share, and
+//! the inner-product proof needs all of the parties' \\(\mathbf{l}\\)
+//! and \\(\mathbf{r}\\) vectors at once, so the peak memory is still
+//! linear in \\(m\\).  What streaming saves is the copy of the vectors
+//! which the batch dealer makes when assembling the proof.  Only the
+//! padding parties' messages are folded into the aggregates without
+//! being kept.
+//!
+//! A streaming dealer cannot be saved, but it can
+//! [abort parties](#party-dropout) like the batch dealer; the
+//! restarted dealer is a batch dealer, which can be switched to
+//! streaming again.
+//!
 //! For more explanation of how the `dealer`, `party`, and `messages` modules orchestrate the protocol execution, see
 //! [the API for the aggregated multiparty computation protocol](../aggregation/index.html#api-for-the-aggregated-multiparty-computation-protocol).
 
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
 use curve25519_dalek::ristretto::RistrettoPoint;
 use curve25519_dalek::scalar::Scalar;
 use merlin::Transcript;
+#[cfg(feature = "std")]
+use rand;
+use rand::prng::ChaChaRng;
+use rand::{CryptoRng, RngCore, SeedableRng};
 
 use errors::MPCError;
 use generators::{BulletproofGens, PedersenGens};
 use inner_product_proof;
-use range_proof::RangeProof;
+use range_proof::{self, RangeProof, ALLOWED_BITSIZES};
 use transcript::TranscriptProtocol;
 
 use util;
 
 use super::messages::*;
+use super::party::{
+    Party, PartyAwaitingBitChallenge, PartyAwaitingPolyChallenge, SavedPartyAwaitingBitChallenge,
+};
 
 /// Used to construct a dealer for the aggregated rangeproof MPC protocol.
 pub struct Dealer {}
@@ -30,17 +102,29 @@ impl Dealer {
         n: usize,
         m: usize,
     ) -> Result<DealerAwaitingBitCommitments<'a, 'b>, MPCError> {
-        if !(n == 8 || n == 16 || n == 32 || n == 64) {
-            return Err(MPCError::InvalidBitsize);
+        if !(n == 8 || n == 16 || n == 32 || n == 64 || n == 128) {
+            return Err(MPCError::InvalidBitsize {
+                got: n,
+                allowed: ALLOWED_BITSIZES,
+            });
         }
         if !m.is_power_of_two() {
-            return Err(MPCError::InvalidAggregation);
+            return Err(MPCError::InvalidAggregation {
+                got: m,
+                reason: "the number of parties must be a power of two",
+            });
         }
         if bp_gens.gens_capacity < n {
-            return Err(MPCError::InvalidGeneratorsLength);
+            return Err(MPCError::InsufficientGeneratorCapacity {
+                needed: n,
+                available: bp_gens.gens_capacity,
+            });
         }
         if bp_gens.party_capacity < m {
-            return Err(MPCError::InvalidGeneratorsLength);
+            return Err(MPCError::InsufficientPartyCapacity {
+                needed: m,
+                available: bp_gens.party_capacity,
+            });
         }
 
         // At the end of the protocol, the dealer will attempt to
@@ -57,19 +141,239 @@ impl Dealer {
         // state.
         let initial_transcript = transcript.clone();
 
-        transcript.rangeproof_domain_sep(n as u64, m as u64);
+        commit_domain_sep(transcript, &vec![n; m], false);
+
+        Ok(DealerAwaitingBitCommitments {
+            bp_gens,
+            pc_gens,
+            transcript,
+            initial_transcript,
+            bitsizes: vec![n; m],
+            m,
+            mixed_bitsizes: false,
+            padding: Vec::new(),
+        })
+    }
+
+    /// Creates a new dealer coordinating parties which prove ranges
+    /// of different bitsizes, where the `j`-th party proves a
+    /// `bitsizes[j]`-bit range.
+    ///
+    /// The `j`-th party must join using
+    /// [`assign_position_with_offset`](::aggregation::party::PartyAwaitingPosition::assign_position_with_offset),
+    /// with the offset `bitsizes[0] + ... + bitsizes[j-1]`.
+    ///
+    /// The number of parties does not need to be a power of two.
+    /// Instead, the dealer pads the aggregation with parties of its
+    /// own, which commit to zero, until the total number of bits is a
+    /// power of two.  Each padding party proves a range no larger than
+    /// the largest of the `bitsizes`, so `bp_gens` must have enough
+    /// party capacity for the real parties plus the padding parties.
+    ///
+    /// The resulting proof is verified with
+    /// [`RangeProof::verify_multiple_with_bitsizes`](::RangeProof::verify_multiple_with_bitsizes).
+    pub fn new_with_bitsizes<'a, 'b>(
+        bp_gens: &'b BulletproofGens,
+        pc_gens: &'b PedersenGens,
+        transcript: &'a mut Transcript,
+        bitsizes: &[usize],
+    ) -> Result<DealerAwaitingBitCommitments<'a, 'b>, MPCError> {
+        if bitsizes.is_empty() {
+            return Err(MPCError::InvalidAggregation {
+                got: 0,
+                reason: "there must be at least one party",
+            });
+        }
+        for &n in bitsizes.iter() {
+            if !(n == 8 || n == 16 || n == 32 || n == 64 || n == 128) {
+                return Err(MPCError::InvalidBitsize {
+                    got: n,
+                    allowed: ALLOWED_BITSIZES,
+                });
+            }
+            if bp_gens.gens_capacity < n {
+                return Err(MPCError::InsufficientGeneratorCapacity {
+                    needed: n,
+                    available: bp_gens.gens_capacity,
+                });
+            }
+        }
+        let m = bitsizes.len();
+        let padding_bitsizes = range_proof::padding_bitsizes(bitsizes);
+        if bp_gens.party_capacity < m + padding_bitsizes.len() {
+            return Err(MPCError::InsufficientPartyCapacity {
+                needed: m + padding_bitsizes.len(),
+                available: bp_gens.party_capacity,
+            });
+        }
+
+        // See the comment in `Dealer::new`.
+        let initial_transcript = transcript.clone();
+
+        commit_domain_sep(transcript, bitsizes, true);
+
+        // The padding parties are played by the dealer, so there is
+        // nothing to hide: they commit to zero with a zero blinding,
+        // and their randomness is derived from the transcript.
+        let mut rng = padding_rng(transcript);
+        let mut padding = Vec::with_capacity(padding_bitsizes.len());
+        let mut offset: usize = bitsizes.iter().sum();
+        for (j, &n) in (m..).zip(padding_bitsizes.iter()) {
+            let party = Party::new(bp_gens, pc_gens, 0, Scalar::zero(), n)?;
+            padding.push(party.assign_position_with_offset_and_rng(j, offset, &mut rng)?);
+            offset += n;
+        }
 
         Ok(DealerAwaitingBitCommitments {
             bp_gens,
             pc_gens,
             transcript,
             initial_transcript,
-            n,
+            bitsizes: bitsizes
+                .iter()
+                .chain(padding_bitsizes.iter())
+                .cloned()
+                .collect(),
             m,
+            mixed_bitsizes: true,
+            padding,
         })
     }
 }
 
+/// Builds the RNG used by the dealer's padding parties.
+///
+/// The padding parties commit to the public value zero, so their
+/// blinding factors need not be secret, and deriving them from the
+/// current transcript state makes the dealer deterministic.
+fn padding_rng(transcript: &Transcript) -> ChaChaRng {
+    let mut seed = [0u8; 32];
+    transcript
+        .clone()
+        .challenge_bytes(b"padding-rng", &mut seed);
+    ChaChaRng::from_seed(seed)
+}
+
+/// Commits the domain separator for an aggregation of parties
+/// proving ranges of the given `bitsizes`, not including padding.
+fn commit_domain_sep(transcript: &mut Transcript, bitsizes: &[usize], mixed_bitsizes: bool) {
+    if mixed_bitsizes {
+        transcript.mixed_rangeproof_domain_sep(bitsizes);
+    } else {
+        transcript.rangeproof_domain_sep(bitsizes[0] as u64, bitsizes.len() as u64);
+    }
+}
+
+/// Commits the parties' [`BitCommitment`]s and computes the
+/// aggregated commitments \\(A, S\\) and the [`BitChallenge`].
+fn commit_bit_commitments(
+    transcript: &mut Transcript,
+    bit_commitments: &[BitCommitment],
+) -> (RistrettoPoint, RistrettoPoint, BitChallenge) {
+    // Commit each V_j individually
+    for vc in bit_commitments.iter() {
+        transcript.commit_point(b"V", &vc.V_j);
+    }
+
+    // Commit aggregated A_j, S_j
+    let A: RistrettoPoint = bit_commitments.iter().map(|vc| vc.A_j).sum();
+    transcript.commit_point(b"A", &A.compress());
+
+    let S: RistrettoPoint = bit_commitments.iter().map(|vc| vc.S_j).sum();
+    transcript.commit_point(b"S", &S.compress());
+
+    let y = transcript.challenge_scalar(b"y");
+    let z = transcript.challenge_scalar(b"z");
+
+    (A, S, BitChallenge { y, z })
+}
+
+/// Commits the parties' [`PolyCommitment`]s and computes the
+/// aggregated commitments \\(T_1, T_2\\) and the [`PolyChallenge`].
+fn commit_poly_commitments(
+    transcript: &mut Transcript,
+    poly_commitments: &[PolyCommitment],
+) -> (RistrettoPoint, RistrettoPoint, PolyChallenge) {
+    // Commit sums of T_1_j's and T_2_j's
+    let T_1: RistrettoPoint = poly_commitments.iter().map(|pc| pc.T_1_j).sum();
+    let T_2: RistrettoPoint = poly_commitments.iter().map(|pc| pc.T_2_j).sum();
+
+    transcript.commit_point(b"T_1", &T_1.compress());
+    transcript.commit_point(b"T_2", &T_2.compress());
+
+    let x = transcript.challenge_scalar(b"x");
+
+    (T_1, T_2, PolyChallenge { x })
+}
+
+/// Restarts the protocol without the parties at the given
+/// `positions`, given the `bitsizes` of the `m` parties (not
+/// including padding).
+fn restart_without_parties<'a, 'b>(
+    bp_gens: &'b BulletproofGens,
+    pc_gens: &'b PedersenGens,
+    transcript: &'a mut Transcript,
+    initial_transcript: Transcript,
+    bitsizes: &[usize],
+    positions: &[usize],
+) -> Result<DealerAwaitingBitCommitments<'a, 'b>, MPCError> {
+    if positions.is_empty() {
+        return Err(MPCError::InvalidAggregation {
+            got: 0,
+            reason: "the positions to abort must be nonempty",
+        });
+    }
+    if let Some(&j) = positions.iter().find(|&&j| j >= bitsizes.len()) {
+        return Err(MPCError::PositionOutOfRange {
+            position: j,
+            num_parties: bitsizes.len(),
+        });
+    }
+
+    let remaining_bitsizes: Vec<usize> = bitsizes
+        .iter()
+        .enumerate()
+        .filter(|&(k, _)| !positions.contains(&k))
+        .map(|(_, &n)| n)
+        .collect();
+    if remaining_bitsizes.is_empty() {
+        return Err(MPCError::NoPartiesRemain);
+    }
+
+    // Rewind the transcript, so that the restarted proof doesn't
+    // depend on the messages of the aborted run.
+    *transcript = initial_transcript;
+
+    Dealer::new_with_bitsizes(bp_gens, pc_gens, transcript, &remaining_bitsizes)
+}
+
+/// Checks that a saved dealer state for parties with the given
+/// `bitsizes`, of which the first `m` are real parties, is
+/// consistent and fits in the generators.
+fn check_saved_state(
+    bp_gens: &BulletproofGens,
+    bitsizes: &[usize],
+    m: usize,
+    num_padding: usize,
+) -> Result<(), MPCError> {
+    if m == 0 || m + num_padding != bitsizes.len() {
+        return Err(MPCError::InvalidSavedState);
+    }
+    if let Some(&n) = bitsizes.iter().find(|&&n| bp_gens.gens_capacity < n) {
+        return Err(MPCError::InsufficientGeneratorCapacity {
+            needed: n,
+            available: bp_gens.gens_capacity,
+        });
+    }
+    if bp_gens.party_capacity < bitsizes.len() {
+        return Err(MPCError::InsufficientPartyCapacity {
+            needed: bitsizes.len(),
+            available: bp_gens.party_capacity,
+        });
+    }
+    Ok(())
+}
+
 /// A dealer waiting for the parties to send their [`BitCommitment`]s.
 pub struct DealerAwaitingBitCommitments<'a, 'b> {
     bp_gens: &'b BulletproofGens,
@@ -78,40 +382,137 @@ pub struct DealerAwaitingBitCommitments<'a, 'b> {
     /// The dealer keeps a copy of the initial transcript state, so
     /// that it can attempt to verify the aggregated proof at the end.
     initial_transcript: Transcript,
-    n: usize,
+    /// Bitsizes of all parties, including the dealer's padding parties
+    bitsizes: Vec<usize>,
+    /// Number of parties, not including the dealer's padding parties
     m: usize,
+    /// Whether the dealer was created with `Dealer::new_with_bitsizes`
+    mixed_bitsizes: bool,
+    /// The dealer's padding parties, with their bit commitments
+    padding: Vec<(PartyAwaitingBitChallenge<'b>, BitCommitment)>,
 }
 
 impl<'a, 'b> DealerAwaitingBitCommitments<'a, 'b> {
+    /// Checks that the [`BitCommitment`] from the party at position
+    /// `j` is well-formed, so that the dealer can reject it as it
+    /// arrives, with the same checks that
+    /// [`receive_bit_commitments`](DealerAwaitingBitCommitments::receive_bit_commitments)
+    /// makes.
+    ///
+    /// The dealer cannot check that the party knows an opening of its
+    /// value commitment \\(V_j\\), only that the commitments are
+    /// valid points; an opening is only proven by the final proof.
+    /// Returns [`MPCError::PositionOutOfRange`] if `j` is not the
+    /// position of one of the parties.
+    pub fn verify_bit_commitment(
+        &self,
+        j: usize,
+        bit_commitment: &BitCommitment,
+    ) -> Result<(), MPCError> {
+        if j >= self.m {
+            return Err(MPCError::PositionOutOfRange {
+                position: j,
+                num_parties: self.m,
+            });
+        }
+        bit_commitment.validate(j)
+    }
+
+    /// Aborts the protocol because the party at position `j` dropped
+    /// out, and restarts it with the remaining parties.
+    ///
+    /// See the [module documentation](index.html#party-dropout) for
+    /// how the parties rejoin and when this is safe.  Returns
+    /// [`MPCError::NoPartiesRemain`] if no parties remain.
+    pub fn abort_party(self, j: usize) -> Result<DealerAwaitingBitCommitments<'a, 'b>, MPCError> {
+        self.abort_parties(&[j])
+    }
+
+    /// Aborts the protocol because the parties at the given
+    /// `positions` dropped out, and restarts it with the remaining
+    /// parties.
+    ///
+    /// Returns [`MPCError::NoPartiesRemain`] if no parties remain,
+    /// [`MPCError::PositionOutOfRange`] if `positions` contains a
+    /// position which is not one of the parties', and
+    /// [`MPCError::InvalidAggregation`] if `positions` is empty.
+    pub fn abort_parties(
+        self,
+        positions: &[usize],
+    ) -> Result<DealerAwaitingBitCommitments<'a, 'b>, MPCError> {
+        restart_without_parties(
+            self.bp_gens,
+            self.pc_gens,
+            self.transcript,
+            self.initial_transcript,
+            &self.bitsizes[..self.m],
+            positions,
+        )
+    }
+
+    /// Saves the dealer's state into a serializable form, which can
+    /// be resumed with [`SavedDealerAwaitingBitCommitments::resume`].
+    pub fn save(self) -> SavedDealerAwaitingBitCommitments {
+        SavedDealerAwaitingBitCommitments {
+            bitsizes: self.bitsizes,
+            m: self.m,
+            mixed_bitsizes: self.mixed_bitsizes,
+            padding: self
+                .padding
+                .into_iter()
+                .map(|(p, c)| (p.save(), c))
+                .collect(),
+        }
+    }
+
+    /// Switches to a [streaming dealer](StreamingDealerAwaitingBitCommitments),
+    /// which absorbs the parties' messages one at a time instead of
+    /// all at once.
+    pub fn streaming(self) -> StreamingDealerAwaitingBitCommitments<'a, 'b> {
+        StreamingDealerAwaitingBitCommitments {
+            bp_gens: self.bp_gens,
+            pc_gens: self.pc_gens,
+            transcript: self.transcript,
+            initial_transcript: self.initial_transcript,
+            bitsizes: self.bitsizes,
+            m: self.m,
+            padding: self.padding,
+            bit_commitments: Vec::with_capacity(self.m),
+        }
+    }
+
     /// Receive each party's [`BitCommitment`]s and compute the [`BitChallenge`].
     pub fn receive_bit_commitments(
         self,
-        bit_commitments: Vec<BitCommitment>,
+        mut bit_commitments: Vec<BitCommitment>,
     ) -> Result<(DealerAwaitingPolyCommitments<'a, 'b>, BitChallenge), MPCError> {
         if self.m != bit_commitments.len() {
-            return Err(MPCError::WrongNumBitCommitments);
+            return Err(MPCError::WrongNumBitCommitments {
+                expected: self.m,
+                actual: bit_commitments.len(),
+            });
         }
-
-        // Commit each V_j individually
-        for vc in bit_commitments.iter() {
-            self.transcript.commit_point(b"V", &vc.V_j);
+        for (j, bit_commitment) in bit_commitments.iter().enumerate() {
+            bit_commitment.validate(j)?;
         }
 
-        // Commit aggregated A_j, S_j
-        let A: RistrettoPoint = bit_commitments.iter().map(|vc| vc.A_j).sum();
-        self.transcript.commit_point(b"A", &A.compress());
+        let (padding, padding_commitments): (Vec<_>, Vec<_>) = self.padding.into_iter().unzip();
+        bit_commitments.extend(padding_commitments);
 
-        let S: RistrettoPoint = bit_commitments.iter().map(|vc| vc.S_j).sum();
-        self.transcript.commit_point(b"S", &S.compress());
+        let (A, S, bit_challenge) = commit_bit_commitments(self.transcript, &bit_commitments);
 
-        let y = self.transcript.challenge_scalar(b"y");
-        let z = self.transcript.challenge_scalar(b"z");
-        let bit_challenge = BitChallenge { y, z };
+        let mut rng = padding_rng(self.transcript);
+        let padding = padding
+            .into_iter()
+            .map(|p| p.apply_challenge_with_rng(&bit_challenge, &mut rng))
+            .collect();
 
         Ok((
             DealerAwaitingPolyCommitments {
-                n: self.n,
+                bitsizes: self.bitsizes,
                 m: self.m,
+                mixed_bitsizes: self.mixed_bitsizes,
+                padding,
                 transcript: self.transcript,
                 initial_transcript: self.initial_transcript,
                 bp_gens: self.bp_gens,
@@ -124,13 +525,81 @@ impl<'a, 'b> DealerAwaitingBitCommitments<'a, 'b> {
             bit_challenge,
         ))
     }
+
+    /// Receive each party's [`BitCommitment`]s together with their
+    /// MACs, as computed by
+    /// [`authenticate_message`](::aggregation::messages::authenticate_message)
+    /// with the party's key in `party_keys`, and compute the
+    /// [`BitChallenge`].
+    ///
+    /// Returns [`MPCError::InvalidMessageMac`] without changing the
+    /// transcript if any MAC is missing or invalid.
+    #[cfg(feature = "mac-messages")]
+    pub fn receive_authenticated_bit_commitments(
+        self,
+        bit_commitments: Vec<BitCommitment>,
+        macs: &[[u8; 32]],
+        party_keys: &[[u8; 32]],
+    ) -> Result<(DealerAwaitingPolyCommitments<'a, 'b>, BitChallenge), MPCError> {
+        verify_message_macs(&bit_commitments, macs, party_keys)?;
+        self.receive_bit_commitments(bit_commitments)
+    }
+}
+
+/// The saved state of a [`DealerAwaitingBitCommitments`].
+///
+/// This contains the secrets of the dealer's padding parties, which
+/// only ever commit to zero, so it need not be stored encrypted.
+#[derive(Serialize, Deserialize)]
+pub struct SavedDealerAwaitingBitCommitments {
+    bitsizes: Vec<usize>,
+    m: usize,
+    mixed_bitsizes: bool,
+    padding: Vec<(SavedPartyAwaitingBitChallenge, BitCommitment)>,
+}
+
+impl SavedDealerAwaitingBitCommitments {
+    /// Resumes the saved state.
+    ///
+    /// The `transcript` must be in the same state as the one the
+    /// dealer was created with, and the generators must be the same.
+    pub fn resume<'a, 'b>(
+        self,
+        bp_gens: &'b BulletproofGens,
+        pc_gens: &'b PedersenGens,
+        transcript: &'a mut Transcript,
+    ) -> Result<DealerAwaitingBitCommitments<'a, 'b>, MPCError> {
+        check_saved_state(bp_gens, &self.bitsizes, self.m, self.padding.len())?;
+
+        let initial_transcript = transcript.clone();
+        commit_domain_sep(transcript, &self.bitsizes[..self.m], self.mixed_bitsizes);
+
+        let padding = self
+            .padding
+            .into_iter()
+            .map(|(p, c)| p.resume(pc_gens).map(|p| (p, c)))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(DealerAwaitingBitCommitments {
+            bp_gens,
+            pc_gens,
+            transcript,
+            initial_transcript,
+            bitsizes: self.bitsizes,
+            m: self.m,
+            mixed_bitsizes: self.mixed_bitsizes,
+            padding,
+        })
+    }
 }
 
 /// A dealer which has sent the [`BitChallenge`] to the parties and
 /// is waiting for their [`PolyCommitment`]s.
 pub struct DealerAwaitingPolyCommitments<'a, 'b> {
-    n: usize,
+    bitsizes: Vec<usize>,
     m: usize,
+    mixed_bitsizes: bool,
+    padding: Vec<(PartyAwaitingPolyChallenge, PolyCommitment)>,
     transcript: &'a mut Transcript,
     initial_transcript: Transcript,
     bp_gens: &'b BulletproofGens,
@@ -144,30 +613,83 @@ pub struct DealerAwaitingPolyCommitments<'a, 'b> {
 }
 
 impl<'a, 'b> DealerAwaitingPolyCommitments<'a, 'b> {
+    /// Aborts the protocol because the party at position `j` dropped
+    /// out, and restarts it with the remaining parties.
+    ///
+    /// See the [module documentation](index.html#party-dropout) for
+    /// how the parties rejoin and when this is safe.  Returns
+    /// [`MPCError::NoPartiesRemain`] if no parties remain.
+    pub fn abort_party(self, j: usize) -> Result<DealerAwaitingBitCommitments<'a, 'b>, MPCError> {
+        self.abort_parties(&[j])
+    }
+
+    /// Aborts the protocol because the parties at the given
+    /// `positions` dropped out, and restarts it with the remaining
+    /// parties.
+    ///
+    /// Returns [`MPCError::NoPartiesRemain`] if no parties remain,
+    /// [`MPCError::PositionOutOfRange`] if `positions` contains a
+    /// position which is not one of the parties', and
+    /// [`MPCError::InvalidAggregation`] if `positions` is empty.
+    pub fn abort_parties(
+        self,
+        positions: &[usize],
+    ) -> Result<DealerAwaitingBitCommitments<'a, 'b>, MPCError> {
+        restart_without_parties(
+            self.bp_gens,
+            self.pc_gens,
+            self.transcript,
+            self.initial_transcript,
+            &self.bitsizes[..self.m],
+            positions,
+        )
+    }
+
+    /// Saves the dealer's state into a serializable form, which can
+    /// be resumed with [`SavedDealerAwaitingPolyCommitments::resume`].
+    pub fn save(self) -> SavedDealerAwaitingPolyCommitments {
+        SavedDealerAwaitingPolyCommitments {
+            bitsizes: self.bitsizes,
+            m: self.m,
+            mixed_bitsizes: self.mixed_bitsizes,
+            padding: self.padding,
+            bit_challenge: self.bit_challenge,
+            bit_commitments: self.bit_commitments,
+        }
+    }
+
     /// Receive [`PolyCommitment`]s from the parties and compute the
     /// [`PolyChallenge`].
     pub fn receive_poly_commitments(
         self,
-        poly_commitments: Vec<PolyCommitment>,
+        mut poly_commitments: Vec<PolyCommitment>,
     ) -> Result<(DealerAwaitingProofShares<'a, 'b>, PolyChallenge), MPCError> {
         if self.m != poly_commitments.len() {
-            return Err(MPCError::WrongNumPolyCommitments);
+            return Err(MPCError::WrongNumPolyCommitments {
+                expected: self.m,
+                actual: poly_commitments.len(),
+            });
+        }
+        for (j, poly_commitment) in poly_commitments.iter().enumerate() {
+            poly_commitment.validate(j)?;
         }
 
-        // Commit sums of T_1_j's and T_2_j's
-        let T_1: RistrettoPoint = poly_commitments.iter().map(|pc| pc.T_1_j).sum();
-        let T_2: RistrettoPoint = poly_commitments.iter().map(|pc| pc.T_2_j).sum();
+        let (padding, padding_commitments): (Vec<_>, Vec<_>) = self.padding.into_iter().unzip();
+        poly_commitments.extend(padding_commitments);
 
-        self.transcript.commit_point(b"T_1", &T_1.compress());
-        self.transcript.commit_point(b"T_2", &T_2.compress());
+        let (T_1, T_2, poly_challenge) = commit_poly_commitments(self.transcript, &poly_commitments);
 
-        let x = self.transcript.challenge_scalar(b"x");
-        let poly_challenge = PolyChallenge { x };
+        let padding_shares = padding
+            .into_iter()
+            .map(|p| p.apply_challenge(&poly_challenge))
+            .collect::<Result<Vec<_>, _>>()?;
 
         Ok((
             DealerAwaitingProofShares {
-                n: self.n,
+                bitsizes: self.bitsizes,
                 m: self.m,
+                mixed_bitsizes: self.mixed_bitsizes,
+                padding_shares,
                 transcript: self.transcript,
                 initial_transcript: self.initial_transcript,
                 bp_gens: self.bp_gens,
@@ -184,14 +706,86 @@ impl<'a, 'b> DealerAwaitingPolyCommitments<'a, 'b> {
             poly_challenge,
         ))
     }
+
+    /// Receive the parties' [`PolyCommitment`]s together with their
+    /// MACs, and compute the [`PolyChallenge`].
+    ///
+    /// The MACs are checked as in
+    /// [`receive_authenticated_bit_commitments`](DealerAwaitingBitCommitments::receive_authenticated_bit_commitments).
+    #[cfg(feature = "mac-messages")]
+    pub fn receive_authenticated_poly_commitments(
+        self,
+        poly_commitments: Vec<PolyCommitment>,
+        macs: &[[u8; 32]],
+        party_keys: &[[u8; 32]],
+    ) -> Result<(DealerAwaitingProofShares<'a, 'b>, PolyChallenge), MPCError> {
+        verify_message_macs(&poly_commitments, macs, party_keys)?;
+        self.receive_poly_commitments(poly_commitments)
+    }
+}
+
+/// The saved state of a [`DealerAwaitingPolyCommitments`].
+#[derive(Serialize, Deserialize)]
+pub struct SavedDealerAwaitingPolyCommitments {
+    bitsizes: Vec<usize>,
+    m: usize,
+    mixed_bitsizes: bool,
+    padding: Vec<(PartyAwaitingPolyChallenge, PolyCommitment)>,
+    bit_challenge: BitChallenge,
+    bit_commitments: Vec<BitCommitment>,
+}
+
+impl SavedDealerAwaitingPolyCommitments {
+    /// Resumes the saved state.
+    ///
+    /// The `transcript` must be in the same state as the one the
+    /// dealer was created with, and the generators must be the same.
+    /// Returns an error if replaying the received messages onto the
+    /// `transcript` does not reproduce the saved challenge.
+    pub fn resume<'a, 'b>(
+        self,
+        bp_gens: &'b BulletproofGens,
+        pc_gens: &'b PedersenGens,
+        transcript: &'a mut Transcript,
+    ) -> Result<DealerAwaitingPolyCommitments<'a, 'b>, MPCError> {
+        check_saved_state(bp_gens, &self.bitsizes, self.m, self.padding.len())?;
+        if self.bit_commitments.len() != self.bitsizes.len() {
+            return Err(MPCError::InvalidSavedState);
+        }
+
+        let initial_transcript = transcript.clone();
+        commit_domain_sep(transcript, &self.bitsizes[..self.m], self.mixed_bitsizes);
+
+        let (A, S, bit_challenge) = commit_bit_commitments(transcript, &self.bit_commitments);
+        if bit_challenge.y != self.bit_challenge.y || bit_challenge.z != self.bit_challenge.z {
+            return Err(MPCError::InvalidSavedState);
+        }
+
+        Ok(DealerAwaitingPolyCommitments {
+            bitsizes: self.bitsizes,
+            m: self.m,
+            mixed_bitsizes: self.mixed_bitsizes,
+            padding: self.padding,
+            transcript,
+            initial_transcript,
+            bp_gens,
+            pc_gens,
+            bit_challenge,
+            bit_commitments: self.bit_commitments,
+            A,
+            S,
+        })
+    }
 }
 
 /// A dealer which has sent the [`PolyChallenge`] to the parties and
 /// is waiting to aggregate their [`ProofShare`]s into a
 /// [`RangeProof`].
 pub struct DealerAwaitingProofShares<'a, 'b> {
-    n: usize,
+    bitsizes: Vec<usize>,
     m: usize,
+    mixed_bitsizes: bool,
+    padding_shares: Vec<ProofShare>,
     transcript: &'a mut Transcript,
     initial_transcript: Transcript,
     bp_gens: &'b BulletproofGens,
@@ -207,6 +801,84 @@ pub struct DealerAwaitingProofShares<'a, 'b> {
 }
 
 impl<'a, 'b> DealerAwaitingProofShares<'a, 'b> {
+    /// Aborts the protocol because the party at position `j` dropped
+    /// out, and restarts it with the remaining parties.
+    ///
+    /// See the [module documentation](index.html#party-dropout) for
+    /// how the parties rejoin and when this is safe.  Returns
+    /// [`MPCError::NoPartiesRemain`] if no parties remain.
+    pub fn abort_party(self, j: usize) -> Result<DealerAwaitingBitCommitments<'a, 'b>, MPCError> {
+        self.abort_parties(&[j])
+    }
+
+    /// Aborts the protocol because the parties at the given
+    /// `positions` dropped out, and restarts it with the remaining
+    /// parties.
+    ///
+    /// Returns [`MPCError::NoPartiesRemain`] if no parties remain,
+    /// [`MPCError::PositionOutOfRange`] if `positions` contains a
+    /// position which is not one of the parties', and
+    /// [`MPCError::InvalidAggregation`] if `positions` is empty.
+    pub fn abort_parties(
+        self,
+        positions: &[usize],
+    ) -> Result<DealerAwaitingBitCommitments<'a, 'b>, MPCError> {
+        restart_without_parties(
+            self.bp_gens,
+            self.pc_gens,
+            self.transcript,
+            self.initial_transcript,
+            &self.bitsizes[..self.m],
+            positions,
+        )
+    }
+
+    /// Saves the dealer's state into a serializable form, which can
+    /// be resumed with [`SavedDealerAwaitingProofShares::resume`].
+    pub fn save(self) -> SavedDealerAwaitingProofShares {
+        SavedDealerAwaitingProofShares {
+            bitsizes: self.bitsizes,
+            m: self.m,
+            mixed_bitsizes: self.mixed_bitsizes,
+            padding_shares: self.padding_shares,
+            bit_challenge: self.bit_challenge,
+            bit_commitments: self.bit_commitments,
+            poly_challenge: self.poly_challenge,
+            poly_commitments: self.poly_commitments,
+        }
+    }
+
+    /// Collects the proof shares which arrived, where
+    /// `proof_shares[j]` is `None` if party `j` did not send its
+    /// share, for passing to
+    /// [`receive_shares`](DealerAwaitingProofShares::receive_shares).
+    ///
+    /// If any shares are missing, returns [`MPCError::PartyDropout`]
+    /// with their positions, which can be passed to
+    /// [`abort_parties`](DealerAwaitingProofShares::abort_parties) to
+    /// restart without them.
+    pub fn collect_shares(
+        &self,
+        proof_shares: &[Option<ProofShare>],
+    ) -> Result<Vec<ProofShare>, MPCError> {
+        if self.m != proof_shares.len() {
+            return Err(MPCError::WrongNumProofShares {
+                expected: self.m,
+                actual: proof_shares.len(),
+            });
+        }
+        let positions: Vec<usize> = proof_shares
+            .iter()
+            .enumerate()
+            .filter(|&(_, share)| share.is_none())
+            .map(|(j, _)| j)
+            .collect();
+        if !positions.is_empty() {
+            return Err(MPCError::PartyDropout { positions });
+        }
+        Ok(proof_shares.iter().cloned().map(Option::unwrap).collect())
+    }
+
     /// Assembles proof shares into an `RangeProof`.
     ///
     /// Used as a helper function by `receive_trusted_shares` (which
@@ -214,9 +886,31 @@ impl<'a, 'b> DealerAwaitingProofShares<'a, 'b> {
     /// validates the proof shares.
     fn assemble_shares(&mut self, proof_shares: &[ProofShare]) -> Result<RangeProof, MPCError> {
         if self.m != proof_shares.len() {
-            return Err(MPCError::WrongNumProofShares);
+            return Err(MPCError::WrongNumProofShares {
+                expected: self.m,
+                actual: proof_shares.len(),
+            });
+        }
+
+        // Shares of the wrong size can't be assembled, so reject them
+        // before building the inner-product proof.
+        let bad_shares: Vec<usize> = proof_shares
+            .iter()
+            .zip(self.bitsizes.iter())
+            .enumerate()
+            .filter(|&(_, (ps, n_j))| ps.l_vec.len() != *n_j || ps.r_vec.len() != *n_j)
+            .map(|(j, _)| j)
+            .collect();
+        if !bad_shares.is_empty() {
+            return Err(MPCError::MalformedProofShares { bad_shares });
         }
 
+        let proof_shares: Vec<&ProofShare> = proof_shares
+            .iter()
+            .chain(self.padding_shares.iter())
+            .collect();
+        let nm: usize = self.bitsizes.iter().sum();
+
         let t_x: Scalar = proof_shares.iter().map(|ps| ps.t_x).sum();
         let t_x_blinding: Scalar = proof_shares.iter().map(|ps| ps.t_x_blinding).sum();
         let e_blinding: Scalar = proof_shares.iter().map(|ps| ps.e_blinding).sum();
@@ -231,7 +925,7 @@ impl<'a, 'b> DealerAwaitingProofShares<'a, 'b> {
         let Q = w * self.pc_gens.B;
 
         let Hprime_factors: Vec<Scalar> = util::exp_iter(self.bit_challenge.y.invert())
-            .take(self.n * self.m)
+            .take(nm)
             .collect();
 
         let l_vec: Vec<Scalar> = proof_shares
@@ -247,8 +941,14 @@ impl<'a, 'b> DealerAwaitingProofShares<'a, 'b> {
             self.transcript,
             &Q,
             &Hprime_factors,
-            self.bp_gens.G(self.n, self.m).cloned().collect(),
-            self.bp_gens.H(self.n, self.m).cloned().collect(),
+            self.bp_gens
+                .G_with_bitsizes(&self.bitsizes)
+                .cloned()
+                .collect(),
+            self.bp_gens
+                .H_with_bitsizes(&self.bitsizes)
+                .cloned()
+                .collect(),
             l_vec,
             r_vec,
         );
@@ -278,26 +978,81 @@ impl<'a, 'b> DealerAwaitingProofShares<'a, 'b> {
     /// performing local aggregation,
     /// [`receive_trusted_shares`](DealerAwaitingProofShares::receive_trusted_shares)
     /// saves time by skipping verification of the aggregated proof.
-    pub fn receive_shares(mut self, proof_shares: &[ProofShare]) -> Result<RangeProof, MPCError> {
+    #[cfg(feature = "std")]
+    pub fn receive_shares(self, proof_shares: &[ProofShare]) -> Result<RangeProof, MPCError> {
+        self.receive_shares_with_rng(proof_shares, &mut rand::thread_rng())
+    }
+
+    /// Assemble the final aggregated [`RangeProof`] from the given
+    /// `proof_shares` and validate it, after checking the MACs on
+    /// the shares as in
+    /// [`receive_authenticated_bit_commitments`](DealerAwaitingBitCommitments::receive_authenticated_bit_commitments).
+    #[cfg(feature = "mac-messages")]
+    pub fn receive_authenticated_shares(
+        self,
+        proof_shares: &[ProofShare],
+        macs: &[[u8; 32]],
+        party_keys: &[[u8; 32]],
+    ) -> Result<RangeProof, MPCError> {
+        verify_message_macs(proof_shares, macs, party_keys)?;
+        self.receive_shares(proof_shares)
+    }
+
+    /// Assemble the final aggregated [`RangeProof`] from the given
+    /// `proof_shares`, then validate the proof using randomness
+    /// drawn from `rng`.
+    ///
+    /// This is the same as
+    /// [`receive_shares`](DealerAwaitingProofShares::receive_shares),
+    /// but is also available without the `std` feature.
+    pub fn receive_shares_with_rng<T: RngCore + CryptoRng>(
+        mut self,
+        proof_shares: &[ProofShare],
+        rng: &mut T,
+    ) -> Result<RangeProof, MPCError> {
         let proof = self.assemble_shares(proof_shares)?;
 
-        let Vs: Vec<_> = self.bit_commitments.iter().map(|vc| vc.V_j).collect();
+        let Vs: Vec<_> = self
+            .bit_commitments
+            .iter()
+            .take(self.m)
+            .map(|vc| vc.V_j)
+            .collect();
 
         // See comment in `Dealer::new` for why we use `initial_transcript`
         let transcript = &mut self.initial_transcript;
-        if proof
-            .verify_multiple(self.bp_gens, self.pc_gens, transcript, &Vs, self.n)
-            .is_ok()
-        {
+        let verification = if self.mixed_bitsizes {
+            proof.verify_multiple_with_bitsizes_and_rng(
+                self.bp_gens,
+                self.pc_gens,
+                transcript,
+                &Vs,
+                &self.bitsizes[..self.m],
+                rng,
+            )
+        } else {
+            proof.verify_multiple_with_rng(
+                self.bp_gens,
+                self.pc_gens,
+                transcript,
+                &Vs,
+                self.bitsizes[0],
+                rng,
+            )
+        };
+
+        if verification.is_ok() {
             Ok(proof)
         } else {
             // Proof verification failed. Now audit the parties:
             let mut bad_shares = Vec::new();
+            let mut offset = 0;
             for j in 0..self.m {
                 match proof_shares[j].audit_share(
                     &self.bp_gens,
                     &self.pc_gens,
                     j,
+                    offset,
                     &self.bit_commitments[j],
                     &self.bit_challenge,
                     &self.poly_commitments[j],
@@ -306,6 +1061,7 @@ impl<'a, 'b> DealerAwaitingProofShares<'a, 'b> {
                     Ok(_) => {}
                     Err(_) => bad_shares.push(j),
                 }
+                offset += self.bitsizes[j];
             }
             Err(MPCError::MalformedProofShares { bad_shares })
         }
@@ -332,3 +1088,486 @@ impl<'a, 'b> DealerAwaitingProofShares<'a, 'b> {
         self.assemble_shares(proof_shares)
     }
 }
+
+/// The saved state of a [`DealerAwaitingProofShares`].
+#[derive(Serialize, Deserialize)]
+pub struct SavedDealerAwaitingProofShares {
+    bitsizes: Vec<usize>,
+    m: usize,
+    mixed_bitsizes: bool,
+    padding_shares: Vec<ProofShare>,
+    bit_challenge: BitChallenge,
+    bit_commitments: Vec<BitCommitment>,
+    poly_challenge: PolyChallenge,
+    poly_commitments: Vec<PolyCommitment>,
+}
+
+impl SavedDealerAwaitingProofShares {
+    /// Resumes the saved state.
+    ///
+    /// The `transcript` must be in the same state as the one the
+    /// dealer was created with, and the generators must be the same.
+    /// Returns an error if replaying the received messages onto the
+    /// `transcript` does not reproduce the saved challenges.
+    pub fn resume<'a, 'b>(
+        self,
+        bp_gens: &'b BulletproofGens,
+        pc_gens: &'b PedersenGens,
+        transcript: &'a mut Transcript,
+    ) -> Result<DealerAwaitingProofShares<'a, 'b>, MPCError> {
+        check_saved_state(bp_gens, &self.bitsizes, self.m, self.padding_shares.len())?;
+        if self.bit_commitments.len() != self.bitsizes.len()
+            || self.poly_commitments.len() != self.bitsizes.len()
+        {
+            return Err(MPCError::InvalidSavedState);
+        }
+
+        let initial_transcript = transcript.clone();
+        commit_domain_sep(transcript, &self.bitsizes[..self.m], self.mixed_bitsizes);
+
+        let (A, S, bit_challenge) = commit_bit_commitments(transcript, &self.bit_commitments);
+        if bit_challenge.y != self.bit_challenge.y || bit_challenge.z != self.bit_challenge.z {
+            return Err(MPCError::InvalidSavedState);
+        }
+
+        let (T_1, T_2, poly_challenge) = commit_poly_commitments(transcript, &self.poly_commitments);
+        if poly_challenge.x != self.poly_challenge.x {
+            return Err(MPCError::InvalidSavedState);
+        }
+
+        Ok(DealerAwaitingProofShares {
+            bitsizes: self.bitsizes,
+            m: self.m,
+            mixed_bitsizes: self.mixed_bitsizes,
+            padding_shares: self.padding_shares,
+            transcript,
+            initial_transcript,
+            bp_gens,
+            pc_gens,
+            bit_challenge,
+            bit_commitments: self.bit_commitments,
+            poly_challenge,
+            poly_commitments: self.poly_commitments,
+            A,
+            S,
+            T_1,
+            T_2,
+        })
+    }
+}
+
+/// Checks that a streamed message comes from the party at the next
+/// position `expected` out of `m` parties.
+fn check_position(j: usize, expected: usize, m: usize) -> Result<(), MPCError> {
+    if j != expected || j >= m {
+        return Err(MPCError::UnexpectedPosition {
+            expected,
+            actual: j,
+        });
+    }
+    Ok(())
+}
+
+/// A streaming dealer absorbing the parties' [`BitCommitment`]s.
+///
+/// See the [module documentation](index.html#streaming).
+pub struct StreamingDealerAwaitingBitCommitments<'a, 'b> {
+    bp_gens: &'b BulletproofGens,
+    pc_gens: &'b PedersenGens,
+    transcript: &'a mut Transcript,
+    initial_transcript: Transcript,
+    bitsizes: Vec<usize>,
+    m: usize,
+    padding: Vec<(PartyAwaitingBitChallenge<'b>, BitCommitment)>,
+    /// The real parties' commitments, kept to audit their proof shares
+    bit_commitments: Vec<BitCommitment>,
+}
+
+impl<'a, 'b> StreamingDealerAwaitingBitCommitments<'a, 'b> {
+    /// Absorbs the [`BitCommitment`] of the party at position `j`.
+    ///
+    /// Returns [`MPCError::UnexpectedPosition`] unless `j` is the
+    /// position after the last absorbed one, which rejects both
+    /// out-of-order and duplicate messages.
+    pub fn absorb_bit_commitment(
+        &mut self,
+        j: usize,
+        bit_commitment: BitCommitment,
+    ) -> Result<(), MPCError> {
+        check_position(j, self.bit_commitments.len(), self.m)?;
+        bit_commitment.validate(j)?;
+
+        self.transcript.commit_point(b"V", &bit_commitment.V_j);
+        self.bit_commitments.push(bit_commitment);
+        Ok(())
+    }
+
+    /// Aborts the protocol because the party at position `j` dropped
+    /// out, and restarts it with the remaining parties, as
+    /// [`DealerAwaitingBitCommitments::abort_party`] does.
+    ///
+    /// The restarted dealer is not streaming; call
+    /// [`streaming`](DealerAwaitingBitCommitments::streaming) on it to
+    /// continue in streaming mode.
+    pub fn abort_party(self, j: usize) -> Result<DealerAwaitingBitCommitments<'a, 'b>, MPCError> {
+        self.abort_parties(&[j])
+    }
+
+    /// Aborts the protocol because the parties at the given
+    /// `positions` dropped out, as
+    /// [`DealerAwaitingBitCommitments::abort_parties`] does.
+    pub fn abort_parties(
+        self,
+        positions: &[usize],
+    ) -> Result<DealerAwaitingBitCommitments<'a, 'b>, MPCError> {
+        restart_without_parties(
+            self.bp_gens,
+            self.pc_gens,
+            self.transcript,
+            self.initial_transcript,
+            &self.bitsizes[..self.m],
+            positions,
+        )
+    }
+
+    /// Computes the [`BitChallenge`] once all parties' commitments
+    /// have been absorbed.
+    pub fn finish(
+        self,
+    ) -> Result<(StreamingDealerAwaitingPolyCommitments<'a, 'b>, BitChallenge), MPCError> {
+        if self.bit_commitments.len() != self.m {
+            return Err(MPCError::WrongNumBitCommitments {
+                expected: self.m,
+                actual: self.bit_commitments.len(),
+            });
+        }
+
+        let mut A: RistrettoPoint = self.bit_commitments.iter().map(|vc| vc.A_j).sum();
+        let mut S: RistrettoPoint = self.bit_commitments.iter().map(|vc| vc.S_j).sum();
+
+        // The padding parties' commitments are only needed for the
+        // aggregate, so they are folded in rather than kept.
+        let mut padding_parties = Vec::with_capacity(self.padding.len());
+        for (party, bit_commitment) in self.padding.into_iter() {
+            self.transcript.commit_point(b"V", &bit_commitment.V_j);
+            A += bit_commitment.A_j;
+            S += bit_commitment.S_j;
+            padding_parties.push(party);
+        }
+
+        self.transcript.commit_point(b"A", &A.compress());
+        self.transcript.commit_point(b"S", &S.compress());
+
+        let y = self.transcript.challenge_scalar(b"y");
+        let z = self.transcript.challenge_scalar(b"z");
+        let bit_challenge = BitChallenge { y, z };
+
+        let mut rng = padding_rng(self.transcript);
+        let padding = padding_parties
+            .into_iter()
+            .map(|p| p.apply_challenge_with_rng(&bit_challenge, &mut rng))
+            .collect();
+
+        Ok((
+            StreamingDealerAwaitingPolyCommitments {
+                bp_gens: self.bp_gens,
+                pc_gens: self.pc_gens,
+                transcript: self.transcript,
+                initial_transcript: self.initial_transcript,
+                bitsizes: self.bitsizes,
+                m: self.m,
+                padding,
+                bit_challenge,
+                bit_commitments: self.bit_commitments,
+                poly_commitments: Vec::with_capacity(self.m),
+                A,
+                S,
+            },
+            bit_challenge,
+        ))
+    }
+}
+
+/// A streaming dealer absorbing the parties' [`PolyCommitment`]s.
+///
+/// See the [module documentation](index.html#streaming).
+pub struct StreamingDealerAwaitingPolyCommitments<'a, 'b> {
+    bp_gens: &'b BulletproofGens,
+    pc_gens: &'b PedersenGens,
+    transcript: &'a mut Transcript,
+    initial_transcript: Transcript,
+    bitsizes: Vec<usize>,
+    m: usize,
+    padding: Vec<(PartyAwaitingPolyChallenge, PolyCommitment)>,
+    bit_challenge: BitChallenge,
+    bit_commitments: Vec<BitCommitment>,
+    /// The real parties' commitments, kept to audit their proof shares
+    poly_commitments: Vec<PolyCommitment>,
+    A: RistrettoPoint,
+    S: RistrettoPoint,
+}
+
+impl<'a, 'b> StreamingDealerAwaitingPolyCommitments<'a, 'b> {
+    /// Absorbs the [`PolyCommitment`] of the party at position `j`.
+    ///
+    /// Returns [`MPCError::UnexpectedPosition`] unless `j` is the
+    /// position after the last absorbed one.
+    pub fn absorb_poly_commitment(
+        &mut self,
+        j: usize,
+        poly_commitment: PolyCommitment,
+    ) -> Result<(), MPCError> {
+        check_position(j, self.poly_commitments.len(), self.m)?;
+        poly_commitment.validate(j)?;
+
+        self.poly_commitments.push(poly_commitment);
+        Ok(())
+    }
+
+    /// Aborts the protocol because the party at position `j` dropped
+    /// out, as
+    /// [`StreamingDealerAwaitingBitCommitments::abort_party`] does.
+    pub fn abort_party(self, j: usize) -> Result<DealerAwaitingBitCommitments<'a, 'b>, MPCError> {
+        self.abort_parties(&[j])
+    }
+
+    /// Aborts the protocol because the parties at the given
+    /// `positions` dropped out, as
+    /// [`DealerAwaitingBitCommitments::abort_parties`] does.
+    pub fn abort_parties(
+        self,
+        positions: &[usize],
+    ) -> Result<DealerAwaitingBitCommitments<'a, 'b>, MPCError> {
+        restart_without_parties(
+            self.bp_gens,
+            self.pc_gens,
+            self.transcript,
+            self.initial_transcript,
+            &self.bitsizes[..self.m],
+            positions,
+        )
+    }
+
+    /// Computes the [`PolyChallenge`] once all parties' commitments
+    /// have been absorbed.
+    pub fn finish(
+        self,
+    ) -> Result<(StreamingDealerAwaitingProofShares<'a, 'b>, PolyChallenge), MPCError> {
+        if self.poly_commitments.len() != self.m {
+            return Err(MPCError::WrongNumPolyCommitments {
+                expected: self.m,
+                actual: self.poly_commitments.len(),
+            });
+        }
+
+        let mut T_1: RistrettoPoint = self.poly_commitments.iter().map(|pc| pc.T_1_j).sum();
+        let mut T_2: RistrettoPoint = self.poly_commitments.iter().map(|pc| pc.T_2_j).sum();
+
+        let mut padding_parties = Vec::with_capacity(self.padding.len());
+        for (party, poly_commitment) in self.padding.into_iter() {
+            T_1 += poly_commitment.T_1_j;
+            T_2 += poly_commitment.T_2_j;
+            padding_parties.push(party);
+        }
+
+        self.transcript.commit_point(b"T_1", &T_1.compress());
+        self.transcript.commit_point(b"T_2", &T_2.compress());
+
+        let x = self.transcript.challenge_scalar(b"x");
+        let poly_challenge = PolyChallenge { x };
+
+        let padding_shares = padding_parties
+            .into_iter()
+            .map(|p| p.apply_challenge(&poly_challenge))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let nm: usize = self.bitsizes.iter().sum();
+
+        Ok((
+            StreamingDealerAwaitingProofShares {
+                bp_gens: self.bp_gens,
+                pc_gens: self.pc_gens,
+                transcript: self.transcript,
+                initial_transcript: self.initial_transcript,
+                bitsizes: self.bitsizes,
+                m: self.m,
+                padding_shares,
+                bit_challenge: self.bit_challenge,
+                bit_commitments: self.bit_commitments,
+                poly_challenge,
+                poly_commitments: self.poly_commitments,
+                A: self.A,
+                S: self.S,
+                T_1,
+                T_2,
+                num_shares: 0,
+                offset: 0,
+                t_x: Scalar::zero(),
+                t_x_blinding: Scalar::zero(),
+                e_blinding: Scalar::zero(),
+                l_vec: Vec::with_capacity(nm),
+                r_vec: Vec::with_capacity(nm),
+            },
+            poly_challenge,
+        ))
+    }
+}
+
+/// A streaming dealer absorbing the parties' [`ProofShare`]s.
+///
+/// See the [module documentation](index.html#streaming).
+pub struct StreamingDealerAwaitingProofShares<'a, 'b> {
+    bp_gens: &'b BulletproofGens,
+    pc_gens: &'b PedersenGens,
+    transcript: &'a mut Transcript,
+    initial_transcript: Transcript,
+    bitsizes: Vec<usize>,
+    m: usize,
+    padding_shares: Vec<ProofShare>,
+    bit_challenge: BitChallenge,
+    bit_commitments: Vec<BitCommitment>,
+    poly_challenge: PolyChallenge,
+    poly_commitments: Vec<PolyCommitment>,
+    A: RistrettoPoint,
+    S: RistrettoPoint,
+    T_1: RistrettoPoint,
+    T_2: RistrettoPoint,
+    /// Number of proof shares absorbed so far
+    num_shares: usize,
+    /// Position of the next party's first bit in the aggregation
+    offset: usize,
+    t_x: Scalar,
+    t_x_blinding: Scalar,
+    e_blinding: Scalar,
+    l_vec: Vec<Scalar>,
+    r_vec: Vec<Scalar>,
+}
+
+impl<'a, 'b> StreamingDealerAwaitingProofShares<'a, 'b> {
+    /// Checks and absorbs the [`ProofShare`] of the party at
+    /// position `j`.
+    ///
+    /// Returns [`MPCError::UnexpectedPosition`] unless `j` is the
+    /// position after the last absorbed one, and
+    /// [`MPCError::MalformedProofShares`] with `j` as the bad share if
+    /// the share is invalid.  A rejected share is not absorbed.
+    pub fn absorb_proof_share(&mut self, j: usize, proof_share: ProofShare) -> Result<(), MPCError> {
+        check_position(j, self.num_shares, self.m)?;
+
+        let n_j = self.bitsizes[j];
+        let well_formed = proof_share.l_vec.len() == n_j
+            && proof_share.r_vec.len() == n_j
+            && proof_share
+                .audit_share(
+                    self.bp_gens,
+                    self.pc_gens,
+                    j,
+                    self.offset,
+                    &self.bit_commitments[j],
+                    &self.bit_challenge,
+                    &self.poly_commitments[j],
+                    &self.poly_challenge,
+                ).is_ok();
+        if !well_formed {
+            return Err(MPCError::MalformedProofShares {
+                bad_shares: vec![j],
+            });
+        }
+
+        self.fold_share(proof_share);
+        Ok(())
+    }
+
+    /// Adds a checked share to the running sums and vectors.
+    fn fold_share(&mut self, proof_share: ProofShare) {
+        self.t_x += proof_share.t_x;
+        self.t_x_blinding += proof_share.t_x_blinding;
+        self.e_blinding += proof_share.e_blinding;
+        self.offset += proof_share.l_vec.len();
+        self.l_vec.extend(proof_share.l_vec);
+        self.r_vec.extend(proof_share.r_vec);
+        self.num_shares += 1;
+    }
+
+    /// Aborts the protocol because the party at position `j` dropped
+    /// out, as
+    /// [`StreamingDealerAwaitingBitCommitments::abort_party`] does.
+    /// The shares absorbed so far are discarded.
+    pub fn abort_party(self, j: usize) -> Result<DealerAwaitingBitCommitments<'a, 'b>, MPCError> {
+        self.abort_parties(&[j])
+    }
+
+    /// Aborts the protocol because the parties at the given
+    /// `positions` dropped out, as
+    /// [`DealerAwaitingBitCommitments::abort_parties`] does.
+    pub fn abort_parties(
+        self,
+        positions: &[usize],
+    ) -> Result<DealerAwaitingBitCommitments<'a, 'b>, MPCError> {
+        restart_without_parties(
+            self.bp_gens,
+            self.pc_gens,
+            self.transcript,
+            self.initial_transcript,
+            &self.bitsizes[..self.m],
+            positions,
+        )
+    }
+
+    /// Assembles the aggregated [`RangeProof`] once all parties'
+    /// shares have been absorbed.
+    pub fn finish(mut self) -> Result<RangeProof, MPCError> {
+        if self.num_shares != self.m {
+            return Err(MPCError::WrongNumProofShares {
+                expected: self.m,
+                actual: self.num_shares,
+            });
+        }
+
+        let padding_shares = ::core::mem::replace(&mut self.padding_shares, Vec::new());
+        for proof_share in padding_shares.into_iter() {
+            self.fold_share(proof_share);
+        }
+        let nm = self.offset;
+
+        self.transcript.commit_scalar(b"t_x", &self.t_x);
+        self.transcript
+            .commit_scalar(b"t_x_blinding", &self.t_x_blinding);
+        self.transcript.commit_scalar(b"e_blinding", &self.e_blinding);
+
+        // Get a challenge value to combine statements for the IPP
+        let w = self.transcript.challenge_scalar(b"w");
+        let Q = w * self.pc_gens.B;
+
+        let Hprime_factors: Vec<Scalar> = util::exp_iter(self.bit_challenge.y.invert())
+            .take(nm)
+            .collect();
+
+        let ipp_proof = inner_product_proof::InnerProductProof::create(
+            self.transcript,
+            &Q,
+            &Hprime_factors,
+            self.bp_gens
+                .G_with_bitsizes(&self.bitsizes)
+                .cloned()
+                .collect(),
+            self.bp_gens
+                .H_with_bitsizes(&self.bitsizes)
+                .cloned()
+                .collect(),
+            self.l_vec,
+            self.r_vec,
+        );
+
+        Ok(RangeProof {
+            A: self.A.compress(),
+            S: self.S.compress(),
+            T_1: self.T_1.compress(),
+            T_2: self.T_2.compress(),
+            t_x: self.t_x,
+            t_x_blinding: self.t_x_blinding,
+            e_blinding: self.e_blinding,
+            ipp_proof,
+        })
+    }
+}
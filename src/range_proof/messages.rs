@@ -1,14 +1,35 @@
 //! The `messages` module contains the API for the messages passed between the parties and the dealer
 //! in an aggregated multiparty computation protocol.
 //!
+//! All of the messages implement `Serialize` and `Deserialize`, so
+//! that they can be sent between the parties and the dealer.  Points
+//! are encoded in compressed Ristretto form and scalars in canonical
+//! form; deserialization rejects invalid points and non-canonical
+//! scalars.
+//!
 //! For more explanation of how the `dealer`, `party`, and `messages` modules orchestrate the protocol execution, see
 //! [the API for the aggregated multiparty computation protocol](../aggregation/index.html#api-for-the-aggregated-multiparty-computation-protocol).
 
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
 use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
 use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::IsIdentity;
+use subtle::{Choice, ConstantTimeEq};
 
+use errors::MPCError;
 use generators::{BulletproofGens, PedersenGens};
 
+#[cfg(feature = "mac-messages")]
+use bincode;
+#[cfg(feature = "mac-messages")]
+use hmac::{Hmac, Mac};
+#[cfg(feature = "mac-messages")]
+use serde::Serialize;
+#[cfg(feature = "mac-messages")]
+use sha2::Sha256;
+
 /// A commitment to the bits of a party's value.
 #[derive(Serialize, Deserialize, Copy, Clone, Debug)]
 pub struct BitCommitment {
@@ -17,6 +38,47 @@ pub struct BitCommitment {
     pub(super) S_j: RistrettoPoint,
 }
 
+impl BitCommitment {
+    /// Checks that the commitments from the party at position `j`
+    /// are valid points, and that \\(A_j\\) and \\(S_j\\) are not
+    /// the identity.
+    pub(super) fn validate(&self, j: usize) -> Result<(), MPCError> {
+        if self.V_j.decompress().is_none() {
+            return Err(MPCError::InvalidPoint { party: j, field: "V" });
+        }
+        if self.A_j.is_identity() {
+            return Err(MPCError::InvalidPoint { party: j, field: "A" });
+        }
+        if self.S_j.is_identity() {
+            return Err(MPCError::InvalidPoint { party: j, field: "S" });
+        }
+        Ok(())
+    }
+
+    /// The party's commitment \\(V_j\\) to their value.
+    pub fn V_j(&self) -> CompressedRistretto {
+        self.V_j
+    }
+
+    /// The party's commitment \\(A_j\\) to the bits of their value.
+    pub fn A_j(&self) -> RistrettoPoint {
+        self.A_j
+    }
+
+    /// The party's commitment \\(S_j\\) to their blinding vectors.
+    pub fn S_j(&self) -> RistrettoPoint {
+        self.S_j
+    }
+}
+
+impl ConstantTimeEq for BitCommitment {
+    fn ct_eq(&self, other: &BitCommitment) -> Choice {
+        self.V_j.as_bytes()[..].ct_eq(&other.V_j.as_bytes()[..])
+            & self.A_j.ct_eq(&other.A_j)
+            & self.S_j.ct_eq(&other.S_j)
+    }
+}
+
 /// Challenge values derived from all parties' [`BitCommitment`]s.
 #[derive(Serialize, Deserialize, Copy, Clone, Debug)]
 pub struct BitChallenge {
@@ -24,6 +86,12 @@ pub struct BitChallenge {
     pub(super) z: Scalar,
 }
 
+impl ConstantTimeEq for BitChallenge {
+    fn ct_eq(&self, other: &BitChallenge) -> Choice {
+        self.y.ct_eq(&other.y) & self.z.ct_eq(&other.z)
+    }
+}
+
 /// A commitment to a party's polynomial coefficents.
 #[derive(Serialize, Deserialize, Copy, Clone, Debug)]
 pub struct PolyCommitment {
@@ -31,12 +99,50 @@ pub struct PolyCommitment {
     pub(super) T_2_j: RistrettoPoint,
 }
 
+impl PolyCommitment {
+    /// Checks that neither of the commitments from the party at
+    /// position `j` is the identity.
+    pub(super) fn validate(&self, j: usize) -> Result<(), MPCError> {
+        if self.T_1_j.is_identity() {
+            return Err(MPCError::InvalidPoint { party: j, field: "T_1" });
+        }
+        if self.T_2_j.is_identity() {
+            return Err(MPCError::InvalidPoint { party: j, field: "T_2" });
+        }
+        Ok(())
+    }
+
+    /// The party's commitment \\(T_{1,j}\\) to the \\(x\\)
+    /// coefficient of their \\(t(x)\\).
+    pub fn T_1_j(&self) -> RistrettoPoint {
+        self.T_1_j
+    }
+
+    /// The party's commitment \\(T_{2,j}\\) to the \\(x^2\\)
+    /// coefficient of their \\(t(x)\\).
+    pub fn T_2_j(&self) -> RistrettoPoint {
+        self.T_2_j
+    }
+}
+
+impl ConstantTimeEq for PolyCommitment {
+    fn ct_eq(&self, other: &PolyCommitment) -> Choice {
+        self.T_1_j.ct_eq(&other.T_1_j) & self.T_2_j.ct_eq(&other.T_2_j)
+    }
+}
+
 /// Challenge values derived from all parties' [`PolyCommitment`]s.
 #[derive(Serialize, Deserialize, Copy, Clone, Debug)]
 pub struct PolyChallenge {
     pub(super) x: Scalar,
 }
 
+impl ConstantTimeEq for PolyChallenge {
+    fn ct_eq(&self, other: &PolyChallenge) -> Choice {
+        self.x.ct_eq(&other.x)
+    }
+}
+
 /// A party's proof share, ready for aggregation into the final
 /// [`RangeProof`](::RangeProof).
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -48,6 +154,16 @@ pub struct ProofShare {
     pub(super) r_vec: Vec<Scalar>,
 }
 
+impl ConstantTimeEq for ProofShare {
+    fn ct_eq(&self, other: &ProofShare) -> Choice {
+        self.t_x.ct_eq(&other.t_x)
+            & self.t_x_blinding.ct_eq(&other.t_x_blinding)
+            & self.e_blinding.ct_eq(&other.e_blinding)
+            & self.l_vec[..].ct_eq(&other.l_vec[..])
+            & self.r_vec[..].ct_eq(&other.r_vec[..])
+    }
+}
+
 impl ProofShare {
     /// Audit an individual proof share to determine whether it is
     /// malformed.
@@ -56,14 +172,15 @@ impl ProofShare {
         bp_gens: &BulletproofGens,
         pc_gens: &PedersenGens,
         j: usize,
+        offset: usize,
         bit_commitment: &BitCommitment,
         bit_challenge: &BitChallenge,
         poly_commitment: &PolyCommitment,
         poly_challenge: &PolyChallenge,
     ) -> Result<(), ()> {
-        use std::iter;
+        use core::iter;
 
-        use curve25519_dalek::traits::{IsIdentity, VartimeMultiscalarMul};
+        use curve25519_dalek::traits::VartimeMultiscalarMul;
 
         use inner_product_proof::inner_product;
         use util;
@@ -76,8 +193,8 @@ impl ProofShare {
         let zz = z * z;
         let minus_z = -z;
         let z_j = util::scalar_exp_vartime(z, j as u64); // z^j
-        let y_jn = util::scalar_exp_vartime(y, (j * n) as u64); // y^(j*n)
-        let y_jn_inv = y_jn.invert(); // y^(-j*n)
+        let y_jn = util::scalar_exp_vartime(y, offset as u64); // y^offset, = y^(j*n) if all parties use n bits
+        let y_jn_inv = y_jn.invert(); // y^(-offset)
         let y_inv = y.invert(); // y^(-1)
 
         if self.t_x != inner_product(&self.l_vec, &self.r_vec) {
@@ -135,3 +252,100 @@ impl ProofShare {
         }
     }
 }
+
+// Messages are equal iff their encodings are equal, and are
+// compared in constant time.
+
+impl PartialEq for BitCommitment {
+    fn eq(&self, other: &BitCommitment) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl Eq for BitCommitment {}
+
+impl PartialEq for BitChallenge {
+    fn eq(&self, other: &BitChallenge) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl Eq for BitChallenge {}
+
+impl PartialEq for PolyCommitment {
+    fn eq(&self, other: &PolyCommitment) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl Eq for PolyCommitment {}
+
+impl PartialEq for PolyChallenge {
+    fn eq(&self, other: &PolyChallenge) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl Eq for PolyChallenge {}
+
+impl PartialEq for ProofShare {
+    fn eq(&self, other: &ProofShare) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl Eq for ProofShare {}
+
+#[cfg(feature = "mac-messages")]
+fn message_hmac<M: Serialize>(msg: &M, key: &[u8; 32]) -> Hmac<Sha256> {
+    let bytes = bincode::serialize(msg).expect("messages are always serializable");
+    let mut mac = Hmac::<Sha256>::new_varkey(key).expect("HMAC accepts keys of any length");
+    mac.input(&bytes);
+    mac
+}
+
+/// Computes an HMAC-SHA256 tag on the bincode encoding of `msg`,
+/// keyed with the 32-byte `key` shared between a party and the
+/// dealer.
+///
+/// The tag authenticates the message contents only.  Binding the
+/// message to a session and a position is left to the key: each
+/// party should use a distinct key for each protocol run.
+#[cfg(feature = "mac-messages")]
+pub fn authenticate_message<M: Serialize>(msg: &M, key: &[u8; 32]) -> [u8; 32] {
+    let mut tag = [0u8; 32];
+    tag.copy_from_slice(&message_hmac(msg, key).result().code());
+    tag
+}
+
+/// Checks, in constant time, that `mac` is the tag computed by
+/// [`authenticate_message`] for `msg` under `key`.
+#[cfg(feature = "mac-messages")]
+pub fn verify_message_mac<M: Serialize>(msg: &M, key: &[u8; 32], mac: &[u8; 32]) -> bool {
+    message_hmac(msg, key).verify(mac).is_ok()
+}
+
+/// Checks the MAC on each party's message, returning
+/// [`MPCError::InvalidMessageMac`] with the positions of the parties
+/// whose MAC or key is missing or whose message fails authentication.
+#[cfg(feature = "mac-messages")]
+pub(super) fn verify_message_macs<M: Serialize>(
+    msgs: &[M],
+    macs: &[[u8; 32]],
+    keys: &[[u8; 32]],
+) -> Result<(), MPCError> {
+    let bad_parties: Vec<usize> = msgs
+        .iter()
+        .enumerate()
+        .filter(|&(j, msg)| match (macs.get(j), keys.get(j)) {
+            (Some(mac), Some(key)) => !verify_message_mac(msg, key, mac),
+            _ => true,
+        }).map(|(j, _)| j)
+        .collect();
+
+    if bad_parties.is_empty() {
+        Ok(())
+    } else {
+        Err(MPCError::InvalidMessageMac { bad_parties })
+    }
+}
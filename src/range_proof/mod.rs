@@ -1,23 +1,34 @@
 #![allow(non_snake_case)]
 #![doc(include = "../docs/range-proof-protocol.md")]
 
+#[cfg(not(feature = "std"))]
+use alloc::string::String;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+#[cfg(feature = "std")]
 use rand;
+use rand::prng::ChaChaRng;
+use rand::{CryptoRng, RngCore, SeedableRng};
+
+use core::iter;
 
-use std::iter;
+use byteorder::{ByteOrder, LittleEndian};
 
 use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
 use curve25519_dalek::scalar::Scalar;
-use curve25519_dalek::traits::{IsIdentity, VartimeMultiscalarMul};
+use curve25519_dalek::traits::{Identity, IsIdentity};
 use merlin::Transcript;
 
-use errors::ProofError;
+use errors::{MPCError, ProofError};
 use generators::{BulletproofGens, PedersenGens};
-use inner_product_proof::InnerProductProof;
+use inner_product_proof::{self, InnerProductProof};
 use transcript::TranscriptProtocol;
 use util;
 
 use serde::de::Visitor;
 use serde::{self, Deserialize, Deserializer, Serialize, Serializer};
+use subtle::{Choice, ConstantTimeEq};
 
 // Modules for MPC protocol
 
@@ -37,10 +48,13 @@ pub mod party;
 /// the verifier.
 ///
 /// This implementation requires that both the bitsize `n` and the
-/// aggregation size `m` be powers of two, so that `n = 8, 16, 32, 64`
+/// aggregation size `m` be powers of two, so that `n = 8, 16, 32, 64, 128`
 /// and `m = 1, 2, 4, 8, 16, ...`.  Note that the aggregation size is
 /// not given as an explicit parameter, but is determined by the
 /// number of values or commitments passed to the prover or verifier.
+/// Aggregated proofs where each party proves its own bitsize can be
+/// created with the MPC protocol and verified with
+/// [`verify_multiple_with_bitsizes`](RangeProof::verify_multiple_with_bitsizes).
 ///
 /// # Note
 ///
@@ -97,7 +111,7 @@ impl RangeProof {
     /// let bp_gens = BulletproofGens::new(64, 1);
     ///
     /// // A secret value we want to prove lies in the range [0, 2^32)
-    /// let secret_value = 1037578891u64;
+    /// let secret_value = 1037578891u128;
     ///
     /// // The API takes a blinding factor for the commitment.
     /// let blinding = Scalar::random(&mut thread_rng());
@@ -125,19 +139,172 @@ impl RangeProof {
     /// );
     /// # }
     /// ```
+    #[cfg(feature = "std")]
     pub fn prove_single(
         bp_gens: &BulletproofGens,
         pc_gens: &PedersenGens,
         transcript: &mut Transcript,
-        v: u64,
+        v: u128,
+        v_blinding: &Scalar,
+        n: usize,
+    ) -> Result<(RangeProof, CompressedRistretto), ProofError> {
+        RangeProof::prove_single_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            v,
+            v_blinding,
+            n,
+            &mut rand::thread_rng(),
+        )
+    }
+
+    /// Create a rangeproof for a given pair of value `v` and
+    /// blinding scalar `v_blinding`, drawing the prover's randomness
+    /// from `rng`.
+    ///
+    /// This is the same as [`RangeProof::prove_single`], but is also
+    /// available without the `std` feature.
+    pub fn prove_single_with_rng<T: RngCore + CryptoRng>(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        v: u128,
         v_blinding: &Scalar,
         n: usize,
+        rng: &mut T,
     ) -> Result<(RangeProof, CompressedRistretto), ProofError> {
-        let (p, Vs) =
-            RangeProof::prove_multiple(bp_gens, pc_gens, transcript, &[v], &[*v_blinding], n)?;
+        let (p, Vs) = RangeProof::prove_multiple_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            &[v],
+            &[*v_blinding],
+            n,
+            rng,
+        )?;
         Ok((p, Vs[0]))
     }
 
+    /// Create a rangeproof for a given pair of value `v` and blinding
+    /// scalar `v_blinding`, from which the holder of `rewind_nonce`
+    /// can recover `v` and `v_blinding` with [`RangeProof::rewind`].
+    ///
+    /// The prover's randomness is derived from the transcript, the
+    /// commitment to `v`, and `rewind_nonce`, with `v` added to the
+    /// blinding factor of \\(A\\).  The proof looks and verifies like
+    /// any other single rangeproof.  The `rewind_nonce` must be kept
+    /// secret, since anyone who knows it can recover the value.
+    pub fn prove_single_rewindable(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        v: u128,
+        v_blinding: &Scalar,
+        n: usize,
+        rewind_nonce: &Scalar,
+    ) -> Result<(RangeProof, CompressedRistretto), ProofError> {
+        use self::dealer::*;
+        use self::party::*;
+
+        if bp_gens.gens_capacity < n {
+            return Err(ProofError::InsufficientGeneratorCapacity {
+                needed: n,
+                available: bp_gens.gens_capacity,
+            });
+        }
+        if bp_gens.party_capacity < 1 {
+            return Err(ProofError::InsufficientPartyCapacity {
+                needed: 1,
+                available: bp_gens.party_capacity,
+            });
+        }
+
+        let V = pc_gens.commit(util::scalar_from_u128(v), *v_blinding).compress();
+        let mut rng = rewind_rng(transcript, &V, rewind_nonce);
+
+        let dealer = Dealer::new(bp_gens, pc_gens, transcript, n, 1)?;
+        let party = Party::new(bp_gens, pc_gens, v, *v_blinding, n)?;
+
+        let (party, bit_commitment) = party.assign_position_with_message_and_rng(
+            0,
+            0,
+            util::scalar_from_u128(v),
+            &mut rng,
+        )?;
+        let (dealer, bit_challenge) = dealer.receive_bit_commitments(vec![bit_commitment])?;
+
+        let (party, poly_commitment) = party.apply_challenge_with_rng(&bit_challenge, &mut rng);
+        let (dealer, poly_challenge) = dealer.receive_poly_commitments(vec![poly_commitment])?;
+
+        let proof_share = party.apply_challenge(&poly_challenge)?;
+        let proof = dealer.receive_trusted_shares(&[proof_share])?;
+
+        Ok((proof, V))
+    }
+
+    /// Recovers the value and blinding factor committed to by `V`
+    /// from a proof created by [`RangeProof::prove_single_rewindable`]
+    /// with the same `rewind_nonce`, transcript and bitsize `n`.
+    ///
+    /// Returns `None` if the nonce, transcript or bitsize differ from
+    /// the prover's, or if the proof was not created rewindably.
+    /// This does not verify the proof, and leaves `transcript`
+    /// unchanged, so that it can still be used to verify.
+    pub fn rewind(
+        &self,
+        pc_gens: &PedersenGens,
+        transcript: &Transcript,
+        V: &CompressedRistretto,
+        n: usize,
+        rewind_nonce: &Scalar,
+    ) -> Option<(u128, Scalar)> {
+        if !ALLOWED_BITSIZES.contains(&n) {
+            return None;
+        }
+
+        // Draw the party's randomness in the same order as the
+        // prover: a_blinding, s_blinding, s_L, s_R, then t_1_blinding
+        // and t_2_blinding.
+        let mut rng = rewind_rng(transcript, V, rewind_nonce);
+        let a_blinding = Scalar::random(&mut rng);
+        let s_blinding = Scalar::random(&mut rng);
+        for _ in 0..2 * n {
+            Scalar::random(&mut rng);
+        }
+        let t_1_blinding = Scalar::random(&mut rng);
+        let t_2_blinding = Scalar::random(&mut rng);
+
+        let mut transcript = transcript.clone();
+        transcript.rangeproof_domain_sep(n as u64, 1);
+        transcript.commit_point(b"V", V);
+        transcript.commit_point(b"A", &self.A);
+        transcript.commit_point(b"S", &self.S);
+        transcript.challenge_scalar(b"y");
+        let z = transcript.challenge_scalar(b"z");
+        transcript.commit_point(b"T_1", &self.T_1);
+        transcript.commit_point(b"T_2", &self.T_2);
+        let x = transcript.challenge_scalar(b"x");
+
+        // e_blinding = (a_blinding + v) + s_blinding * x
+        let message = self.e_blinding - s_blinding * x - a_blinding;
+        if message.as_bytes()[16..].iter().any(|&b| b != 0) {
+            return None;
+        }
+        let v_lo = LittleEndian::read_u64(&message.as_bytes()[..8]) as u128;
+        let v_hi = LittleEndian::read_u64(&message.as_bytes()[8..16]) as u128;
+        let v = v_lo | (v_hi << 64);
+
+        // t_x_blinding = z^2 * v_blinding + t_1_blinding * x + t_2_blinding * x^2
+        let v_blinding =
+            (self.t_x_blinding - t_1_blinding * x - t_2_blinding * x * x) * (z * z).invert();
+
+        if pc_gens.commit(util::scalar_from_u128(v), v_blinding).compress() != *V {
+            return None;
+        }
+        Some((v, v_blinding))
+    }
+
     /// Create a rangeproof for a set of values.
     ///
     /// # Example
@@ -164,7 +331,7 @@ impl RangeProof {
     /// let bp_gens = BulletproofGens::new(64, 16);
     ///
     /// // Four secret values we want to prove lie in the range [0, 2^32)
-    /// let secrets = [4242344947u64, 3718732727u64, 2255562556u64, 2526146994u64];
+    /// let secrets = [4242344947u128, 3718732727u128, 2255562556u128, 2526146994u128];
     ///
     /// // The API takes blinding factors for the commitments.
     /// let blindings: Vec<_> = (0..4).map(|_| Scalar::random(&mut thread_rng())).collect();
@@ -192,19 +359,60 @@ impl RangeProof {
     /// );
     /// # }
     /// ```
+    #[cfg(feature = "std")]
     pub fn prove_multiple(
         bp_gens: &BulletproofGens,
         pc_gens: &PedersenGens,
         transcript: &mut Transcript,
-        values: &[u64],
+        values: &[u128],
+        blindings: &[Scalar],
+        n: usize,
+    ) -> Result<(RangeProof, Vec<CompressedRistretto>), ProofError> {
+        RangeProof::prove_multiple_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            values,
+            blindings,
+            n,
+            &mut rand::thread_rng(),
+        )
+    }
+
+    /// Create a rangeproof for a set of values, drawing the prover's
+    /// randomness from `rng`.
+    ///
+    /// This is the same as [`RangeProof::prove_multiple`], but is
+    /// also available without the `std` feature.
+    pub fn prove_multiple_with_rng<T: RngCore + CryptoRng>(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        values: &[u128],
         blindings: &[Scalar],
         n: usize,
+        rng: &mut T,
     ) -> Result<(RangeProof, Vec<CompressedRistretto>), ProofError> {
         use self::dealer::*;
         use self::party::*;
 
         if values.len() != blindings.len() {
-            return Err(ProofError::WrongNumBlindingFactors);
+            return Err(ProofError::WrongNumBlindingFactors {
+                expected: values.len(),
+                actual: blindings.len(),
+            });
+        }
+        if bp_gens.gens_capacity < n {
+            return Err(ProofError::InsufficientGeneratorCapacity {
+                needed: n,
+                available: bp_gens.gens_capacity,
+            });
+        }
+        if bp_gens.party_capacity < values.len() {
+            return Err(ProofError::InsufficientPartyCapacity {
+                needed: values.len(),
+                available: bp_gens.party_capacity,
+            });
         }
 
         let dealer = Dealer::new(bp_gens, pc_gens, transcript, n, values.len())?;
@@ -220,7 +428,7 @@ impl RangeProof {
             .into_iter()
             .enumerate()
             .map(|(j, p)| {
-                p.assign_position(j)
+                p.assign_position_with_rng(j, rng)
                     .expect("We already checked the parameters, so this should never happen")
             }).unzip();
 
@@ -230,7 +438,107 @@ impl RangeProof {
 
         let (parties, poly_commitments): (Vec<_>, Vec<_>) = parties
             .into_iter()
-            .map(|p| p.apply_challenge(&bit_challenge))
+            .map(|p| p.apply_challenge_with_rng(&bit_challenge, rng))
+            .unzip();
+
+        let (dealer, poly_challenge) = dealer.receive_poly_commitments(poly_commitments)?;
+
+        let proof_shares: Vec<_> = parties
+            .into_iter()
+            .map(|p| p.apply_challenge(&poly_challenge))
+            // Collect the iterator of Results into a Result<Vec>, then unwrap it
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let proof = dealer.receive_trusted_shares(&proof_shares)?;
+
+        Ok((proof, value_commitments))
+    }
+
+    /// Create an aggregated rangeproof for a set of values, where the
+    /// `j`-th value is proven to lie in \\([0, 2^{n_j})\\), with
+    /// \\(n_j\\) given by `bitsizes[j]`.
+    ///
+    /// The aggregation is padded as described in
+    /// [`Dealer::new_with_bitsizes`](::aggregation::dealer::Dealer::new_with_bitsizes),
+    /// so `bp_gens` must have enough party capacity for the padding.
+    /// The proof is verified with
+    /// [`RangeProof::verify_multiple_with_bitsizes`].
+    #[cfg(feature = "std")]
+    pub fn prove_multiple_with_bitsizes(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        values: &[u128],
+        blindings: &[Scalar],
+        bitsizes: &[usize],
+    ) -> Result<(RangeProof, Vec<CompressedRistretto>), ProofError> {
+        RangeProof::prove_multiple_with_bitsizes_and_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            values,
+            blindings,
+            bitsizes,
+            &mut rand::thread_rng(),
+        )
+    }
+
+    /// Create an aggregated rangeproof with mixed bitsizes, drawing
+    /// the prover's randomness from `rng`.
+    ///
+    /// This is the same as [`RangeProof::prove_multiple_with_bitsizes`],
+    /// but is also available without the `std` feature.
+    pub fn prove_multiple_with_bitsizes_and_rng<T: RngCore + CryptoRng>(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        values: &[u128],
+        blindings: &[Scalar],
+        bitsizes: &[usize],
+        rng: &mut T,
+    ) -> Result<(RangeProof, Vec<CompressedRistretto>), ProofError> {
+        use self::dealer::*;
+        use self::party::*;
+
+        if values.len() != blindings.len() {
+            return Err(ProofError::WrongNumBlindingFactors {
+                expected: values.len(),
+                actual: blindings.len(),
+            });
+        }
+        if values.len() != bitsizes.len() {
+            return Err(ProofError::WrongNumBitsizes {
+                expected: values.len(),
+                actual: bitsizes.len(),
+            });
+        }
+
+        let dealer = Dealer::new_with_bitsizes(bp_gens, pc_gens, transcript, bitsizes)?;
+
+        let mut parties = Vec::with_capacity(values.len());
+        let mut bit_commitments = Vec::with_capacity(values.len());
+        let mut offset = 0;
+        for (j, ((&v, &v_blinding), &n)) in values
+            .iter()
+            .zip(blindings.iter())
+            .zip(bitsizes.iter())
+            .enumerate()
+        {
+            let party = Party::new(bp_gens, pc_gens, v, v_blinding, n)?;
+            let (party, bit_commitment) =
+                party.assign_position_with_offset_and_rng(j, offset, rng)?;
+            parties.push(party);
+            bit_commitments.push(bit_commitment);
+            offset += n;
+        }
+
+        let value_commitments: Vec<_> = bit_commitments.iter().map(|c| c.V_j).collect();
+
+        let (dealer, bit_challenge) = dealer.receive_bit_commitments(bit_commitments)?;
+
+        let (parties, poly_commitments): (Vec<_>, Vec<_>) = parties
+            .into_iter()
+            .map(|p| p.apply_challenge_with_rng(&bit_challenge, rng))
             .unzip();
 
         let (dealer, poly_challenge) = dealer.receive_poly_commitments(poly_commitments)?;
@@ -249,6 +557,7 @@ impl RangeProof {
     /// Verifies a rangeproof for a given value commitment \\(V\\).
     ///
     /// This is a convenience wrapper around `verify_multiple` for the `m=1` case.
+    #[cfg(feature = "std")]
     pub fn verify_single(
         &self,
         bp_gens: &BulletproofGens,
@@ -260,7 +569,25 @@ impl RangeProof {
         self.verify_multiple(bp_gens, pc_gens, transcript, &[*V], n)
     }
 
+    /// Verifies a rangeproof for a given value commitment \\(V\\),
+    /// drawing the verifier's randomness from `rng`.
+    ///
+    /// This is the same as [`RangeProof::verify_single`], but is also
+    /// available without the `std` feature.
+    pub fn verify_single_with_rng<T: RngCore + CryptoRng>(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        V: &CompressedRistretto,
+        n: usize,
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        self.verify_multiple_with_rng(bp_gens, pc_gens, transcript, &[*V], n, rng)
+    }
+
     /// Verifies an aggregated rangeproof for the given value commitments.
+    #[cfg(feature = "std")]
     pub fn verify_multiple(
         &self,
         bp_gens: &BulletproofGens,
@@ -268,23 +595,327 @@ impl RangeProof {
         transcript: &mut Transcript,
         value_commitments: &[CompressedRistretto],
         n: usize,
+    ) -> Result<(), ProofError> {
+        self.verify_multiple_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            value_commitments,
+            n,
+            &mut rand::thread_rng(),
+        )
+    }
+
+    /// Verifies an aggregated rangeproof for the given value
+    /// commitments, drawing the verifier's randomness from `rng`.
+    ///
+    /// This is the same as [`RangeProof::verify_multiple`], but is
+    /// also available without the `std` feature.
+    pub fn verify_multiple_with_rng<T: RngCore + CryptoRng>(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        value_commitments: &[CompressedRistretto],
+        n: usize,
+        rng: &mut T,
     ) -> Result<(), ProofError> {
         let m = value_commitments.len();
 
         // First, replay the "interactive" protocol using the proof
         // data to recompute all challenges.
-        if !(n == 8 || n == 16 || n == 32 || n == 64) {
-            return Err(ProofError::InvalidBitsize);
+        if !(n == 8 || n == 16 || n == 32 || n == 64 || n == 128) {
+            return Err(ProofError::InvalidBitsize {
+                got: n,
+                allowed: ALLOWED_BITSIZES,
+            });
         }
         if bp_gens.gens_capacity < n {
-            return Err(ProofError::InvalidGeneratorsLength);
+            return Err(ProofError::InsufficientGeneratorCapacity {
+                needed: n,
+                available: bp_gens.gens_capacity,
+            });
+        }
+        if !m.is_power_of_two() {
+            return Err(ProofError::InvalidAggregation {
+                got: m,
+                reason: "the number of commitments must be a power of two",
+            });
         }
         if bp_gens.party_capacity < m {
-            return Err(ProofError::InvalidGeneratorsLength);
+            return Err(ProofError::InsufficientPartyCapacity {
+                needed: m,
+                available: bp_gens.party_capacity,
+            });
         }
 
         transcript.rangeproof_domain_sep(n as u64, m as u64);
 
+        self.verify_aggregated(
+            bp_gens,
+            pc_gens,
+            transcript,
+            value_commitments,
+            &vec![n; m],
+            rng,
+        )
+    }
+
+    /// Verifies an aggregated rangeproof in which the `j`-th value
+    /// commitment was proven to be in the range \\([0, 2^{n_j})\\),
+    /// where \\(n_j\\) is `bitsizes[j]`.
+    ///
+    /// Such proofs are created with the MPC protocol, using
+    /// [`Dealer::new_with_bitsizes`](::aggregation::dealer::Dealer::new_with_bitsizes).
+    /// The bitsizes are bound to the transcript, so a proof does not
+    /// verify if the bitsizes are permuted or substituted.
+    ///
+    /// Since the dealer pads the aggregation with parties of its own,
+    /// `bp_gens` must have the party capacity given by
+    /// [`Dealer::new_with_bitsizes`](::aggregation::dealer::Dealer::new_with_bitsizes).
+    #[cfg(feature = "std")]
+    pub fn verify_multiple_with_bitsizes(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        value_commitments: &[CompressedRistretto],
+        bitsizes: &[usize],
+    ) -> Result<(), ProofError> {
+        self.verify_multiple_with_bitsizes_and_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            value_commitments,
+            bitsizes,
+            &mut rand::thread_rng(),
+        )
+    }
+
+    /// Verifies an aggregated rangeproof with mixed bitsizes, drawing
+    /// the verifier's randomness from `rng`.
+    ///
+    /// This is the same as
+    /// [`RangeProof::verify_multiple_with_bitsizes`], but is also
+    /// available without the `std` feature.
+    pub fn verify_multiple_with_bitsizes_and_rng<T: RngCore + CryptoRng>(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        value_commitments: &[CompressedRistretto],
+        bitsizes: &[usize],
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        if value_commitments.len() != bitsizes.len() {
+            return Err(ProofError::WrongNumBitsizes {
+                expected: value_commitments.len(),
+                actual: bitsizes.len(),
+            });
+        }
+        if bitsizes.is_empty() {
+            return Err(ProofError::InvalidAggregation {
+                got: 0,
+                reason: "there must be at least one commitment",
+            });
+        }
+        for &n in bitsizes.iter() {
+            if !(n == 8 || n == 16 || n == 32 || n == 64 || n == 128) {
+                return Err(ProofError::InvalidBitsize {
+                    got: n,
+                    allowed: ALLOWED_BITSIZES,
+                });
+            }
+            if bp_gens.gens_capacity < n {
+                return Err(ProofError::InsufficientGeneratorCapacity {
+                    needed: n,
+                    available: bp_gens.gens_capacity,
+                });
+            }
+        }
+        let padding = padding_bitsizes(bitsizes);
+        if bp_gens.party_capacity < bitsizes.len() + padding.len() {
+            return Err(ProofError::InsufficientPartyCapacity {
+                needed: bitsizes.len() + padding.len(),
+                available: bp_gens.party_capacity,
+            });
+        }
+
+        transcript.mixed_rangeproof_domain_sep(bitsizes);
+
+        // The padding parties commit to zero with a zero blinding factor.
+        let all_bitsizes: Vec<usize> = bitsizes.iter().chain(padding.iter()).cloned().collect();
+        let all_commitments: Vec<CompressedRistretto> = value_commitments
+            .iter()
+            .cloned()
+            .chain(iter::repeat(CompressedRistretto::identity()).take(padding.len()))
+            .collect();
+
+        self.verify_aggregated(
+            bp_gens,
+            pc_gens,
+            transcript,
+            &all_commitments,
+            &all_bitsizes,
+            rng,
+        )
+    }
+
+    /// Create a proof that the value `v` committed to with blinding
+    /// factor `v_blinding` lies in the interval \\([min, max]\\).
+    ///
+    /// The proof is an aggregated rangeproof that \\(v - min\\) and
+    /// \\(max - v\\) both lie in \\([0, 2^n)\\), where \\(n\\) is the
+    /// smallest bitsize with \\(max - min < 2^n\\), so at most
+    /// \\(128\\).  Both differences are committed to by shifting the
+    /// commitment to \\(v\\), so the returned commitment is the
+    /// ordinary commitment to \\(v\\).  The bounds are committed to
+    /// the transcript.
+    ///
+    /// `bp_gens` must have a party capacity of at least 2, and a
+    /// generator capacity of at least \\(n\\).
+    ///
+    /// Returns [`ProofError::InvalidInterval`] if \\(min > max\\) or if
+    /// `v` lies outside the interval.
+    #[cfg(feature = "std")]
+    pub fn prove_interval(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        v: u128,
+        v_blinding: &Scalar,
+        min: u128,
+        max: u128,
+    ) -> Result<(RangeProof, CompressedRistretto), ProofError> {
+        RangeProof::prove_interval_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            v,
+            v_blinding,
+            min,
+            max,
+            &mut rand::thread_rng(),
+        )
+    }
+
+    /// Create a proof that the value `v` lies in the interval
+    /// \\([min, max]\\), drawing the prover's randomness from `rng`.
+    ///
+    /// This is the same as [`RangeProof::prove_interval`], but is
+    /// also available without the `std` feature.
+    pub fn prove_interval_with_rng<T: RngCore + CryptoRng>(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        v: u128,
+        v_blinding: &Scalar,
+        min: u128,
+        max: u128,
+        rng: &mut T,
+    ) -> Result<(RangeProof, CompressedRistretto), ProofError> {
+        if min > max || v < min || v > max {
+            return Err(ProofError::InvalidInterval);
+        }
+        let n = interval_bitsize(min, max);
+
+        transcript.interval_domain_sep(min, max);
+
+        // Commit to v - min with blinding v_blinding, and to max - v
+        // with blinding -v_blinding, so that the verifier can derive
+        // both commitments from the commitment to v.
+        let (proof, _) = RangeProof::prove_multiple_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            &[v - min, max - v],
+            &[*v_blinding, -v_blinding],
+            n,
+            rng,
+        )?;
+
+        Ok((proof, pc_gens.commit(util::scalar_from_u128(v), *v_blinding).compress()))
+    }
+
+    /// Verifies a proof that the value committed to by `V` lies in the
+    /// interval \\([min, max]\\).
+    ///
+    /// Returns [`ProofError::InvalidInterval`] if \\(min > max\\).
+    #[cfg(feature = "std")]
+    pub fn verify_interval(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        V: &CompressedRistretto,
+        min: u128,
+        max: u128,
+    ) -> Result<(), ProofError> {
+        self.verify_interval_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            V,
+            min,
+            max,
+            &mut rand::thread_rng(),
+        )
+    }
+
+    /// Verifies a proof that the value committed to by `V` lies in the
+    /// interval \\([min, max]\\), drawing the verifier's randomness
+    /// from `rng`.
+    ///
+    /// This is the same as [`RangeProof::verify_interval`], but is
+    /// also available without the `std` feature.
+    pub fn verify_interval_with_rng<T: RngCore + CryptoRng>(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        V: &CompressedRistretto,
+        min: u128,
+        max: u128,
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        if min > max {
+            return Err(ProofError::InvalidInterval);
+        }
+        let n = interval_bitsize(min, max);
+
+        let V = V
+            .decompress()
+            .ok_or(ProofError::PointDecompressionError { which: "V" })?;
+        let V_min = V - pc_gens.B * util::scalar_from_u128(min);
+        let V_max = pc_gens.B * util::scalar_from_u128(max) - V;
+
+        transcript.interval_domain_sep(min, max);
+
+        self.verify_multiple_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            &[V_min.compress(), V_max.compress()],
+            n,
+            rng,
+        )
+    }
+
+    /// Verifies the aggregated proof after the domain separator has
+    /// been committed to the transcript, where the `j`-th party
+    /// proved a `bitsizes[j]`-bit range for `value_commitments[j]`.
+    fn verify_aggregated<T: RngCore + CryptoRng>(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        value_commitments: &[CompressedRistretto],
+        bitsizes: &[usize],
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        let m = bitsizes.len();
+        let nm: usize = bitsizes.iter().sum();
+
         for V in value_commitments.iter() {
             transcript.commit_point(b"V", V);
         }
@@ -307,24 +938,29 @@ impl RangeProof {
 
         let w = transcript.challenge_scalar(b"w");
 
-        let mut rng = transcript.build_rng().finalize(&mut rand::thread_rng());
+        let mut rng = transcript.build_rng().finalize(rng);
 
         // Challenge value for batching statements to be verified
         let c = Scalar::random(&mut rng);
 
-        let (x_sq, x_inv_sq, s) = self.ipp_proof.verification_scalars(transcript);
+        let (x_sq, x_inv_sq, s) = self.ipp_proof.verification_scalars(nm, transcript)?;
         let s_inv = s.iter().rev();
 
         let a = self.ipp_proof.a;
         let b = self.ipp_proof.b;
 
         // Construct concat_z_and_2, an iterator of the values of
-        // z^0 * \vec(2)^n || z^1 * \vec(2)^n || ... || z^(m-1) * \vec(2)^n
-        let powers_of_2: Vec<Scalar> = util::exp_iter(Scalar::from(2u64)).take(n).collect();
+        // z^0 * \vec(2)^n_0 || z^1 * \vec(2)^n_1 || ... || z^(m-1) * \vec(2)^n_(m-1)
+        let max_n = bitsizes.iter().cloned().max().unwrap_or(0);
+        let powers_of_2: Vec<Scalar> = util::exp_iter(Scalar::from(2u64)).take(max_n).collect();
         let concat_z_and_2: Vec<Scalar> = util::exp_iter(z)
-            .take(m)
-            .flat_map(|exp_z| powers_of_2.iter().map(move |exp_2| exp_2 * exp_z))
-            .collect();
+            .zip(bitsizes.iter())
+            .flat_map(|(exp_z, &n_j)| {
+                powers_of_2
+                    .iter()
+                    .take(n_j)
+                    .map(move |exp_2| exp_2 * exp_z)
+            }).collect();
 
         let g = s.iter().map(|s_i| minus_z - a * s_i);
         let h = s_inv
@@ -333,9 +969,34 @@ impl RangeProof {
             .map(|((s_i_inv, exp_y_inv), z_and_2)| z + exp_y_inv * (zz * z_and_2 - b * s_i_inv));
 
         let value_commitment_scalars = util::exp_iter(z).take(m).map(|z_exp| c * zz * z_exp);
-        let basepoint_scalar = w * (self.t_x - a * b) + c * (delta(n, m, &y, &z) - self.t_x);
+        let basepoint_scalar = w * (self.t_x - a * b) + c * (delta(bitsizes, &y, &z) - self.t_x);
+
+        let decompress = |P: &CompressedRistretto, which: &'static str| {
+            P.decompress()
+                .ok_or(ProofError::PointDecompressionError { which })
+        };
+        let A = decompress(&self.A, "A")?;
+        let S = decompress(&self.S, "S")?;
+        let T_1 = decompress(&self.T_1, "T_1")?;
+        let T_2 = decompress(&self.T_2, "T_2")?;
+        let Ls = self
+            .ipp_proof
+            .L_vec
+            .iter()
+            .map(|L| decompress(L, "L"))
+            .collect::<Result<Vec<_>, _>>()?;
+        let Rs = self
+            .ipp_proof
+            .R_vec
+            .iter()
+            .map(|R| decompress(R, "R"))
+            .collect::<Result<Vec<_>, _>>()?;
+        let Vs = value_commitments
+            .iter()
+            .map(|V| decompress(V, "V"))
+            .collect::<Result<Vec<_>, _>>()?;
 
-        let mega_check = RistrettoPoint::optional_multiscalar_mul(
+        let mega_check = util::vartime_multiscalar_mul(
             iter::once(Scalar::one())
                 .chain(iter::once(x))
                 .chain(iter::once(c * x))
@@ -347,18 +1008,18 @@ impl RangeProof {
                 .chain(g)
                 .chain(h)
                 .chain(value_commitment_scalars),
-            iter::once(self.A.decompress())
-                .chain(iter::once(self.S.decompress()))
-                .chain(iter::once(self.T_1.decompress()))
-                .chain(iter::once(self.T_2.decompress()))
-                .chain(self.ipp_proof.L_vec.iter().map(|L| L.decompress()))
-                .chain(self.ipp_proof.R_vec.iter().map(|R| R.decompress()))
-                .chain(iter::once(Some(pc_gens.B_blinding)))
-                .chain(iter::once(Some(pc_gens.B)))
-                .chain(bp_gens.G(n, m).map(|&x| Some(x)))
-                .chain(bp_gens.H(n, m).map(|&x| Some(x)))
-                .chain(value_commitments.iter().map(|V| V.decompress())),
-        ).ok_or_else(|| ProofError::VerificationError)?;
+            iter::once(&A)
+                .chain(iter::once(&S))
+                .chain(iter::once(&T_1))
+                .chain(iter::once(&T_2))
+                .chain(Ls.iter())
+                .chain(Rs.iter())
+                .chain(iter::once(&pc_gens.B_blinding))
+                .chain(iter::once(&pc_gens.B))
+                .chain(bp_gens.G_with_bitsizes(bitsizes))
+                .chain(bp_gens.H_with_bitsizes(bitsizes))
+                .chain(Vs.iter()),
+        );
 
         if mega_check.is_identity() {
             Ok(())
@@ -378,6 +1039,9 @@ impl RangeProof {
     /// * three scalars \\(t_x, \tilde{t}_x, \tilde{e}\\),
     /// * \\(n\\) pairs of compressed Ristretto points \\(L_0,R_0\dots,L_{n-1},R_{n-1}\\),
     /// * two scalars \\(a, b\\).
+    ///
+    /// The encoding does not depend on serde, and is stable: the
+    /// serde implementations for `RangeProof` wrap these bytes.
     pub fn to_bytes(&self) -> Vec<u8> {
         // 7 elements: points A, S, T1, T2, scalars tx, tx_bl, e_bl.
         let mut buf = Vec::with_capacity(7 * 32 + self.ipp_proof.serialized_size());
@@ -395,14 +1059,13 @@ impl RangeProof {
 
     /// Deserializes the proof from a byte slice.
     ///
-    /// Returns an error if the byte slice cannot be parsed into a `RangeProof`.
+    /// Returns [`ProofError::WrongProofLength`] if the slice is not
+    /// \\(2k+9\\) 32-byte elements for some number of rounds \\(k\\),
+    /// [`ProofError::WrongNumRounds`] if \\(k\\) is 32 or more, and
+    /// [`ProofError::FormatError`] if a scalar is not canonical.
     pub fn from_bytes(slice: &[u8]) -> Result<RangeProof, ProofError> {
-        if slice.len() % 32 != 0 {
-            return Err(ProofError::FormatError);
-        }
-        if slice.len() < 7 * 32 {
-            return Err(ProofError::FormatError);
-        }
+        // 7 elements, then the inner-product proof's 2k + 2.
+        inner_product_proof::parse_num_rounds(slice.len(), 9)?;
 
         use util::read32;
 
@@ -431,26 +1094,99 @@ impl RangeProof {
             ipp_proof,
         })
     }
-}
 
-impl Serialize for RangeProof {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        serializer.serialize_bytes(&self.to_bytes()[..])
+    /// Serializes the proof into a lowercase hex string of its
+    /// [`to_bytes`](RangeProof::to_bytes) encoding.
+    pub fn to_hex(&self) -> String {
+        util::to_hex(&self.to_bytes())
     }
-}
 
-impl<'de> Deserialize<'de> for RangeProof {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: Deserializer<'de>,
-    {
-        struct RangeProofVisitor;
+    /// Deserializes the proof from a hex string.
+    ///
+    /// Returns [`ProofError::FormatError`] if the string is not valid
+    /// hex, and the errors of [`from_bytes`](RangeProof::from_bytes)
+    /// if the bytes cannot be parsed into a `RangeProof`.
+    pub fn from_hex(s: &str) -> Result<RangeProof, ProofError> {
+        let bytes = util::from_hex(s).ok_or(ProofError::FormatError)?;
+        RangeProof::from_bytes(&bytes)
+    }
 
-        impl<'de> Visitor<'de> for RangeProofVisitor {
-            type Value = RangeProof;
+    /// The commitment \\(A\\) to the bits of the values.
+    pub fn A(&self) -> CompressedRistretto {
+        self.A
+    }
+
+    /// The commitment \\(S\\) to the blinding vectors.
+    pub fn S(&self) -> CompressedRistretto {
+        self.S
+    }
+
+    /// The commitment \\(T_1\\) to the \\(x\\) coefficient of \\(t(x)\\).
+    pub fn T_1(&self) -> CompressedRistretto {
+        self.T_1
+    }
+
+    /// The commitment \\(T_2\\) to the \\(x^2\\) coefficient of \\(t(x)\\).
+    pub fn T_2(&self) -> CompressedRistretto {
+        self.T_2
+    }
+
+    /// The evaluation \\(t(x)\\) of the polynomial at the challenge point.
+    pub fn t_x(&self) -> Scalar {
+        self.t_x
+    }
+
+    /// The blinding factor for the synthetic commitment to \\(t(x)\\).
+    pub fn t_x_blinding(&self) -> Scalar {
+        self.t_x_blinding
+    }
+
+    /// The blinding factor for the synthetic commitment to the
+    /// inner-product arguments.
+    pub fn e_blinding(&self) -> Scalar {
+        self.e_blinding
+    }
+
+    /// The inner-product proof.
+    pub fn ipp_proof(&self) -> &InnerProductProof {
+        &self.ipp_proof
+    }
+}
+
+/// Two proofs are equal if their encodings are equal; they are
+/// compared in constant time.
+impl ConstantTimeEq for RangeProof {
+    fn ct_eq(&self, other: &RangeProof) -> Choice {
+        self.to_bytes()[..].ct_eq(&other.to_bytes()[..])
+    }
+}
+
+impl PartialEq for RangeProof {
+    fn eq(&self, other: &RangeProof) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl Eq for RangeProof {}
+
+impl Serialize for RangeProof {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_bytes(&self.to_bytes()[..])
+    }
+}
+
+impl<'de> Deserialize<'de> for RangeProof {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct RangeProofVisitor;
+
+        impl<'de> Visitor<'de> for RangeProofVisitor {
+            type Value = RangeProof;
 
             fn expecting(&self, formatter: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
                 formatter.write_str("a valid RangeProof")
@@ -468,27 +1204,185 @@ impl<'de> Deserialize<'de> for RangeProof {
     }
 }
 
+/// Runs the aggregated rangeproof MPC protocol in-process, with one
+/// party for each of the `values` and their `blindings`, and returns
+/// the proof and the parties' value commitments.
+///
+/// This drives the [`dealer`] and [`party`] states through the same
+/// steps as a distributed execution.  Unlike
+/// [`RangeProof::prove_multiple`], the dealer verifies the proof
+/// shares, so an invalid share is reported as
+/// [`MPCError::MalformedProofShares`] with the positions of the
+/// offending parties.
+///
+/// Returns [`MPCError::WrongNumBlindingFactors`] if the number of
+/// `blindings` differs from the number of `values`.
+#[cfg(feature = "std")]
+pub fn aggregate_locally(
+    bp_gens: &BulletproofGens,
+    pc_gens: &PedersenGens,
+    transcript: &mut Transcript,
+    values: &[u128],
+    blindings: &[Scalar],
+    n: usize,
+) -> Result<(RangeProof, Vec<CompressedRistretto>), MPCError> {
+    aggregate_locally_with_rng(
+        bp_gens,
+        pc_gens,
+        transcript,
+        values,
+        blindings,
+        n,
+        &mut rand::thread_rng(),
+    )
+}
+
+/// Runs the aggregated rangeproof MPC protocol in-process, drawing
+/// the parties' and the dealer's randomness from `rng`.
+///
+/// This is the same as [`aggregate_locally`], but is also available
+/// without the `std` feature.
+pub fn aggregate_locally_with_rng<T: RngCore + CryptoRng>(
+    bp_gens: &BulletproofGens,
+    pc_gens: &PedersenGens,
+    transcript: &mut Transcript,
+    values: &[u128],
+    blindings: &[Scalar],
+    n: usize,
+    rng: &mut T,
+) -> Result<(RangeProof, Vec<CompressedRistretto>), MPCError> {
+    use self::dealer::*;
+    use self::party::*;
+
+    if values.len() != blindings.len() {
+        return Err(MPCError::WrongNumBlindingFactors {
+            expected: values.len(),
+            actual: blindings.len(),
+        });
+    }
+
+    let dealer = Dealer::new(bp_gens, pc_gens, transcript, n, values.len())?;
+
+    let (parties, bit_commitments): (Vec<_>, Vec<_>) = values
+        .iter()
+        .zip(blindings.iter())
+        .enumerate()
+        .map(|(j, (&v, &v_blinding))| {
+            Party::new(bp_gens, pc_gens, v, v_blinding, n)?.assign_position_with_rng(j, rng)
+        })
+        // Collect the iterator of Results into a Result<Vec>, then unwrap it
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
+        .unzip();
+
+    let value_commitments: Vec<_> = bit_commitments.iter().map(|c| c.V_j).collect();
+
+    let (dealer, bit_challenge) = dealer.receive_bit_commitments(bit_commitments)?;
+
+    let (parties, poly_commitments): (Vec<_>, Vec<_>) = parties
+        .into_iter()
+        .map(|p| p.apply_challenge_with_rng(&bit_challenge, rng))
+        .unzip();
+
+    let (dealer, poly_challenge) = dealer.receive_poly_commitments(poly_commitments)?;
+
+    let proof_shares: Vec<_> = parties
+        .into_iter()
+        .map(|p| p.apply_challenge(&poly_challenge))
+        // Collect the iterator of Results into a Result<Vec>, then unwrap it
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let proof = dealer.receive_shares_with_rng(&proof_shares, rng)?;
+
+    Ok((proof, value_commitments))
+}
+
+/// Returns the RNG for the prover's randomness in a rewindable
+/// proof, derived from the initial transcript, the value commitment
+/// `V` and the `rewind_nonce`.
+fn rewind_rng(
+    transcript: &Transcript,
+    V: &CompressedRistretto,
+    rewind_nonce: &Scalar,
+) -> ChaChaRng {
+    let mut transcript = transcript.clone();
+    transcript.commit_point(b"rewind-V", V);
+    transcript.commit_scalar(b"rewind-nonce", rewind_nonce);
+
+    let mut seed = [0u8; 32];
+    transcript.challenge_bytes(b"rewind-rng", &mut seed);
+    ChaChaRng::from_seed(seed)
+}
+
+/// Returns the smallest supported bitsize \\(n\\) such that
+/// \\(max - min < 2^n\\).
+fn interval_bitsize(min: u128, max: u128) -> usize {
+    let width = max - min;
+    [8, 16, 32, 64]
+        .iter()
+        .cloned()
+        .find(|&n| width >> n == 0)
+        .unwrap_or(128)
+}
+
 /// Compute
 /// \\[
-/// \delta(y,z) = (z - z^{2}) \langle \mathbf{1}, {\mathbf{y}}^{n \cdot m} \rangle - \sum_{j=0}^{m-1} z^{j+3} \cdot \langle \mathbf{1}, {\mathbf{2}}^{n \cdot m} \rangle
+/// \delta(y,z) = (z - z^{2}) \langle \mathbf{1}, {\mathbf{y}}^{N} \rangle - \sum_{j=0}^{m-1} z^{j+3} \cdot \langle \mathbf{1}, {\mathbf{2}}^{n_j} \rangle
 /// \\]
-fn delta(n: usize, m: usize, y: &Scalar, z: &Scalar) -> Scalar {
-    let sum_y = util::sum_of_powers(y, n * m);
-    let sum_2 = util::sum_of_powers(&Scalar::from(2u64), n);
-    let sum_z = util::sum_of_powers(z, m);
+/// where \\(n_j\\) is the bitsize of the \\(j\\)-th party and
+/// \\(N = \sum_j n_j\\).  When all parties use the same bitsize
+/// \\(n\\), this is \\((z - z^{2}) \langle \mathbf{1}, {\mathbf{y}}^{n \cdot m} \rangle - z^3 \langle \mathbf{1}, {\mathbf{2}}^{n} \rangle \langle \mathbf{1}, {\mathbf{z}}^{m} \rangle\\).
+fn delta(bitsizes: &[usize], y: &Scalar, z: &Scalar) -> Scalar {
+    let nm: usize = bitsizes.iter().sum();
+    let sum_y = util::sum_of_powers(y, nm);
+    let sum_z_and_2: Scalar = util::exp_iter(*z)
+        .zip(bitsizes.iter())
+        .map(|(exp_z, &n_j)| exp_z * util::sum_of_powers(&Scalar::from(2u64), n_j))
+        .sum();
+
+    (z - z * z) * sum_y - z * z * z * sum_z_and_2
+}
 
-    (z - z * z) * sum_y - z * z * z * sum_2 * sum_z
+/// The bitsizes \\(n\\) for which rangeproofs can be created.
+pub(crate) const ALLOWED_BITSIZES: &[usize] = &[8, 16, 32, 64, 128];
+
+/// Returns the bitsizes of the parties the dealer appends to an
+/// aggregation with the given `bitsizes`, so that the total number
+/// of bits is a power of two.
+///
+/// Each padding party commits to zero with a zero blinding factor
+/// and proves a range no larger than the largest of the `bitsizes`.
+pub(crate) fn padding_bitsizes(bitsizes: &[usize]) -> Vec<usize> {
+    let max_n = bitsizes.iter().cloned().max().unwrap_or(0);
+    let nm: usize = bitsizes.iter().sum();
+
+    let mut remaining = nm.next_power_of_two() - nm;
+    let mut padding = Vec::new();
+    while remaining > 0 {
+        let n = [64, 32, 16, 8]
+            .iter()
+            .cloned()
+            .find(|&n| n <= remaining && n <= max_n)
+            .expect("bitsizes are multiples of 8, so the padding is too");
+        padding.push(n);
+        remaining -= n;
+    }
+    padding
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[cfg(not(feature = "std"))]
+    use alloc::string::ToString;
+
+    use errors;
     use generators::PedersenGens;
 
     #[test]
     fn test_delta() {
-        let mut rng = rand::thread_rng();
+        let mut rng = util::deterministic_rng([10u8; 32]);
         let y = Scalar::random(&mut rng);
         let z = Scalar::random(&mut rng);
 
@@ -509,7 +1403,7 @@ mod tests {
             exp_2 = exp_2 + exp_2; // 2^i -> 2^(i+1)
         }
 
-        assert_eq!(power_g, delta(n, 1, &y, &z),);
+        assert_eq!(power_g, delta(&[n], &y, &z),);
     }
 
     /// Given a bitsize `n`, test the following:
@@ -519,6 +1413,7 @@ mod tests {
     /// 3. Deserialize from wire format;
     /// 4. Verify the proof.
     fn singleparty_create_and_verify_helper(n: usize, m: usize) {
+        let mut rng = util::deterministic_rng([11u8; 32]);
         // Split the test into two scopes, so that it's explicit what
         // data is shared between the prover and the verifier.
 
@@ -534,28 +1429,29 @@ mod tests {
         // Prover's scope
         let (proof_bytes, value_commitments) = {
             use rand::Rng;
-            let mut rng = rand::thread_rng();
 
             // 0. Create witness data
             let (min, max) = (0u64, ((1u128 << n) - 1) as u64);
-            let values: Vec<u64> = (0..m).map(|_| rng.gen_range(min, max)).collect();
+            let values: Vec<u128> = (0..m).map(|_| rng.gen_range(min, max) as u128).collect();
             let blindings: Vec<Scalar> = (0..m).map(|_| Scalar::random(&mut rng)).collect();
 
             // 1. Create the proof
             let mut transcript = Transcript::new(b"AggregatedRangeProofTest");
-            let (proof, value_commitments) = RangeProof::prove_multiple(
+            let (proof, value_commitments) = RangeProof::prove_multiple_with_rng(
                 &bp_gens,
                 &pc_gens,
                 &mut transcript,
                 &values,
                 &blindings,
                 n,
+                &mut rng,
             ).unwrap();
 
             // 2. Return serialized proof and value commitments
             (bincode::serialize(&proof).unwrap(), value_commitments)
         };
 
+        #[cfg(feature = "std")]
         println!(
             "Aggregated rangeproof of m={} proofs of n={} bits has size {} bytes",
             m,
@@ -573,8 +1469,14 @@ mod tests {
 
             assert!(
                 proof
-                    .verify_multiple(&bp_gens, &pc_gens, &mut transcript, &value_commitments, n)
-                    .is_ok()
+                    .verify_multiple_with_rng(
+                        &bp_gens,
+                        &pc_gens,
+                        &mut transcript,
+                        &value_commitments,
+                        n,
+                        &mut rng
+                    ).is_ok()
             );
         }
     }
@@ -620,118 +1522,2508 @@ mod tests {
     }
 
     #[test]
-    fn detect_dishonest_party_during_aggregation() {
+    fn create_and_verify_n_128() {
+        let mut rng = util::deterministic_rng([12u8; 32]);
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(128, 2);
+        let blinding = Scalar::from(5u64);
+        let transcript = || Transcript::new(b"U128RangeProofTest");
+
+        let (proof, V) = RangeProof::prove_single_with_rng(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript(),
+            u128::max_value(),
+            &blinding,
+            128,
+            &mut rng,
+        ).unwrap();
+        assert_eq!(
+            V,
+            pc_gens
+                .commit(util::scalar_from_u128(u128::max_value()), blinding)
+                .compress()
+        );
+        assert!(
+            proof
+                .verify_single_with_rng(&bp_gens, &pc_gens, &mut transcript(), &V, 128, &mut rng)
+                .is_ok()
+        );
+
+        // u128::MAX doesn't fit in 64 bits.
+        let (proof, V) = RangeProof::prove_single_with_rng(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript(),
+            u128::max_value(),
+            &blinding,
+            64,
+            &mut rng,
+        ).unwrap();
+        assert!(
+            proof
+                .verify_single_with_rng(&bp_gens, &pc_gens, &mut transcript(), &V, 64, &mut rng)
+                .is_err()
+        );
+
+        // An aggregated proof of two 128-bit values has lg(256) = 8
+        // inner-product rounds.
+        let values = [u128::max_value(), 1 << 100];
+        let blindings = [Scalar::from(6u64), Scalar::from(7u64)];
+        let (proof, value_commitments) = RangeProof::prove_multiple_with_rng(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript(),
+            &values,
+            &blindings,
+            128,
+            &mut rng,
+        ).unwrap();
+
+        let bytes = proof.to_bytes();
+        assert_eq!(bytes.len(), (9 + 2 * 8) * 32);
+        let proof = RangeProof::from_bytes(&bytes).unwrap();
+        assert!(
+            proof
+                .verify_multiple_with_rng(
+                    &bp_gens,
+                    &pc_gens,
+                    &mut transcript(),
+                    &value_commitments,
+                    128,
+                    &mut rng
+                ).is_ok()
+        );
+    }
+
+    /// Runs the MPC protocol with a dealer expecting `bitsizes`, where
+    /// the `j`-th party proves that `values[j]` fits in `party_bitsizes[j]`
+    /// bits, and returns the proof and value commitments.
+    fn mixed_bitsizes_helper(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        values: &[u128],
+        party_bitsizes: &[usize],
+        bitsizes: &[usize],
+    ) -> Result<(RangeProof, Vec<CompressedRistretto>), errors::MPCError> {
         use self::dealer::*;
         use self::party::*;
 
-        use errors::MPCError;
+        let mut rng = util::deterministic_rng([13u8; 32]);
+        let mut transcript = Transcript::new(b"MixedRangeProofTest");
+
+        let dealer = Dealer::new_with_bitsizes(bp_gens, pc_gens, &mut transcript, bitsizes)?;
+
+        let mut parties = Vec::new();
+        let mut bit_commitments = Vec::new();
+        let mut offset = 0;
+        for (j, (&v, &n)) in values.iter().zip(party_bitsizes.iter()).enumerate() {
+            let party = Party::new(bp_gens, pc_gens, v, Scalar::random(&mut rng), n)?;
+            let (party, bit_commitment) =
+                party.assign_position_with_offset_and_rng(j, offset, &mut rng)?;
+            parties.push(party);
+            bit_commitments.push(bit_commitment);
+            offset += bitsizes[j];
+        }
+        let value_commitments: Vec<_> = bit_commitments.iter().map(|c| c.V_j).collect();
 
-        // Simulate four parties, two of which will be dishonest and use a 64-bit value.
-        let m = 4;
-        let n = 32;
+        let (dealer, bit_challenge) = dealer.receive_bit_commitments(bit_commitments)?;
+
+        let (parties, poly_commitments): (Vec<_>, Vec<_>) = parties
+            .into_iter()
+            .map(|p| p.apply_challenge_with_rng(&bit_challenge, &mut rng))
+            .unzip();
+
+        let (dealer, poly_challenge) = dealer.receive_poly_commitments(poly_commitments)?;
+
+        let proof_shares = parties
+            .into_iter()
+            .map(|p| p.apply_challenge(&poly_challenge))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let proof = dealer.receive_shares_with_rng(&proof_shares, &mut rng)?;
 
+        Ok((proof, value_commitments))
+    }
+
+    #[test]
+    fn create_and_verify_mixed_bitsizes() {
         let pc_gens = PedersenGens::default();
-        let bp_gens = BulletproofGens::new(n, m);
+        // 8 + 32 + 64 = 104 bits are padded to 128 by two dealer
+        // parties of 16 and 8 bits, so we need capacity for 5 parties.
+        let bp_gens = BulletproofGens::new(64, 5);
+        let bitsizes = [8, 32, 64];
+        assert_eq!(padding_bitsizes(&bitsizes), vec![16, 8]);
 
         use rand::Rng;
-        let mut rng = rand::thread_rng();
-        let mut transcript = Transcript::new(b"AggregatedRangeProofTest");
+        let mut rng = util::deterministic_rng([14u8; 32]);
+        let values = [rng.gen::<u8>() as u128, rng.gen::<u32>() as u128, rng.gen::<u64>() as u128];
+
+        let (proof, value_commitments) =
+            mixed_bitsizes_helper(&bp_gens, &pc_gens, &values, &bitsizes, &bitsizes).unwrap();
+
+        let mut transcript = Transcript::new(b"MixedRangeProofTest");
+        assert!(
+            proof
+                .verify_multiple_with_bitsizes_and_rng(
+                    &bp_gens,
+                    &pc_gens,
+                    &mut transcript,
+                    &value_commitments,
+                    &bitsizes,
+                    &mut rng
+                ).is_ok()
+        );
 
-        // Parties 0, 2 are honest and use a 32-bit value
-        let v0 = rng.gen::<u32>() as u64;
-        let v0_blinding = Scalar::random(&mut rng);
-        let party0 = Party::new(&bp_gens, &pc_gens, v0, v0_blinding, n).unwrap();
+        // The bitsizes are bound to the transcript, so the proof
+        // doesn't verify for any other assignment of ranges.
+        for other_bitsizes in [[8, 64, 32], [64, 32, 8], [8, 32, 32], [8, 64, 64]].iter() {
+            let mut transcript = Transcript::new(b"MixedRangeProofTest");
+            assert!(
+                proof
+                    .verify_multiple_with_bitsizes_and_rng(
+                        &bp_gens,
+                        &pc_gens,
+                        &mut transcript,
+                        &value_commitments,
+                        other_bitsizes,
+                        &mut rng
+                    ).is_err()
+            );
+        }
+    }
 
-        let v2 = rng.gen::<u32>() as u64;
-        let v2_blinding = Scalar::random(&mut rng);
-        let party2 = Party::new(&bp_gens, &pc_gens, v2, v2_blinding, n).unwrap();
+    #[test]
+    fn detect_mixed_bitsize_substitution() {
+        use errors::MPCError;
 
-        // Parties 1, 3 are dishonest and use a 64-bit value
-        let v1 = rng.gen::<u64>();
-        let v1_blinding = Scalar::random(&mut rng);
-        let party1 = Party::new(&bp_gens, &pc_gens, v1, v1_blinding, n).unwrap();
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 5);
+        let bitsizes = [8, 32, 64];
 
-        let v3 = rng.gen::<u64>();
-        let v3_blinding = Scalar::random(&mut rng);
-        let party3 = Party::new(&bp_gens, &pc_gens, v3, v3_blinding, n).unwrap();
+        use rand::Rng;
+        let mut rng = util::deterministic_rng([15u8; 32]);
+        let v1 = (rng.gen::<u32>() as u128) | (1 << 40);
+        let values = [rng.gen::<u8>() as u128, v1, rng.gen::<u64>() as u128];
+
+        // Party 1 tries to fill its 32-bit slot with a 64-bit proof.
+        match mixed_bitsizes_helper(&bp_gens, &pc_gens, &values, &[8, 64, 64], &bitsizes) {
+            Err(MPCError::MalformedProofShares { bad_shares }) => assert_eq!(bad_shares, vec![1]),
+            _ => panic!("The substituted bitsize was not detected"),
+        }
 
-        let dealer = Dealer::new(&bp_gens, &pc_gens, &mut transcript, n, m).unwrap();
+        // Party 1 proves a 32-bit range for a value which doesn't fit.
+        match mixed_bitsizes_helper(&bp_gens, &pc_gens, &values, &bitsizes, &bitsizes) {
+            Err(MPCError::MalformedProofShares { bad_shares }) => assert_eq!(bad_shares, vec![1]),
+            _ => panic!("The out-of-range value was not detected"),
+        }
+    }
 
-        let (party0, bit_com0) = party0.assign_position(0).unwrap();
-        let (party1, bit_com1) = party1.assign_position(1).unwrap();
-        let (party2, bit_com2) = party2.assign_position(2).unwrap();
-        let (party3, bit_com3) = party3.assign_position(3).unwrap();
+    #[test]
+    fn proof_is_bound_to_application_context() {
+        let mut rng = util::deterministic_rng([16u8; 32]);
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 1);
+        let blinding = Scalar::random(&mut rng);
+
+        let transcript_with_context = |context: &[u8]| {
+            let mut transcript = Transcript::new(b"ContextRangeProofTest");
+            transcript.commit_bytes(b"dom-sep", b"payment");
+            transcript.commit_bytes(b"tx-id", context);
+            transcript
+        };
 
-        let (dealer, bit_challenge) = dealer
-            .receive_bit_commitments(vec![bit_com0, bit_com1, bit_com2, bit_com3])
-            .unwrap();
+        let (proof, V) = RangeProof::prove_single_with_rng(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript_with_context(b"tx-A"),
+            1037578891,
+            &blinding,
+            32,
+            &mut rng,
+        ).unwrap();
+
+        assert!(
+            proof
+                .verify_single_with_rng(
+                    &bp_gens,
+                    &pc_gens,
+                    &mut transcript_with_context(b"tx-A"),
+                    &V,
+                    32,
+                    &mut rng
+                ).is_ok()
+        );
+        assert!(
+            proof
+                .verify_single_with_rng(
+                    &bp_gens,
+                    &pc_gens,
+                    &mut transcript_with_context(b"tx-B"),
+                    &V,
+                    32,
+                    &mut rng
+                ).is_err()
+        );
+    }
 
-        let (party0, poly_com0) = party0.apply_challenge(&bit_challenge);
-        let (party1, poly_com1) = party1.apply_challenge(&bit_challenge);
-        let (party2, poly_com2) = party2.apply_challenge(&bit_challenge);
-        let (party3, poly_com3) = party3.apply_challenge(&bit_challenge);
+    #[test]
+    fn proving_is_reproducible_with_seeded_rng() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 2);
+        let values = [1037578891u128, 7];
+        let blindings = [Scalar::from(11u64), Scalar::from(13u64)];
 
-        let (dealer, poly_challenge) = dealer
-            .receive_poly_commitments(vec![poly_com0, poly_com1, poly_com2, poly_com3])
-            .unwrap();
+        let prove = |seed: [u8; 32]| {
+            let (proof, _) = RangeProof::prove_multiple_with_rng(
+                &bp_gens,
+                &pc_gens,
+                &mut Transcript::new(b"SeededRngTest"),
+                &values,
+                &blindings,
+                32,
+                &mut util::deterministic_rng(seed),
+            ).unwrap();
+            proof.to_bytes()
+        };
 
-        let share0 = party0.apply_challenge(&poly_challenge).unwrap();
-        let share1 = party1.apply_challenge(&poly_challenge).unwrap();
-        let share2 = party2.apply_challenge(&poly_challenge).unwrap();
-        let share3 = party3.apply_challenge(&poly_challenge).unwrap();
+        assert_eq!(prove([1u8; 32]), prove([1u8; 32]));
+        assert!(prove([1u8; 32]) != prove([2u8; 32]));
 
-        match dealer.receive_shares(&[share0, share1, share2, share3]) {
-            Err(MPCError::MalformedProofShares { bad_shares }) => {
-                assert_eq!(bad_shares, vec![1, 3]);
-            }
-            Err(_) => {
-                panic!("Got wrong error type from malformed shares");
-            }
-            Ok(_) => {
-                panic!("The proof was malformed, but it was not detected");
-            }
+        let proof = RangeProof::from_bytes(&prove([1u8; 32])).unwrap();
+        let Vs: Vec<_> = values
+            .iter()
+            .zip(blindings.iter())
+            .map(|(&v, &r)| pc_gens.commit(util::scalar_from_u128(v), r).compress())
+            .collect();
+        assert!(
+            proof
+                .verify_multiple_with_rng(
+                    &bp_gens,
+                    &pc_gens,
+                    &mut Transcript::new(b"SeededRngTest"),
+                    &Vs,
+                    32,
+                    &mut util::deterministic_rng([3u8; 32]),
+                ).is_ok()
+        );
+    }
+
+    #[test]
+    fn seeded_proof_is_the_same_on_every_backend() {
+        // The backends must agree on the encodings of points and
+        // scalars, so a seeded proof has the same bytes whichever
+        // backend built it.  CI runs this under each backend.
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 1);
+
+        let (proof, V) = RangeProof::prove_single_with_rng(
+            &bp_gens,
+            &pc_gens,
+            &mut Transcript::new(b"BackendTest"),
+            1037578891u128,
+            &Scalar::from(1037578892u64),
+            64,
+            &mut util::deterministic_rng([5u8; 32]),
+        ).unwrap();
+
+        let expected = util::from_hex(concat!(
+            "a4f090b6a2de1385739b780deb24f5d972786f8db5556e6ca974fd3f76b46c51",
+            "2e12a6037a9279bc23a3d63c2039c402b28d567737dbc907252871d66861dd7b",
+            "9034e610d0c73ab51e12d0aa994f02163bc87bf510fb9f5c7b6f79391e2af911",
+            "26f5718e9da3c0548bbb1a140a1a3d42bf3c490c8b9e84cceb22217679d6db32",
+            "f1d8b2bf5055f1f30be204326781071cfe905f084a19fa896e2433d5c7b50204",
+            "785409f2752e543714a3e28e27060fbeb2e8c52bc039a0ec0779a2c1a40a8403",
+            "99bac1cc2231bc4256f60d08baf47725d1c6852f4e4c16d09efef0407eb92007",
+            "b8766b6c97fe366afe1d88563d0d86dad42669f515f6be3e7f4ba57af4608413",
+            "16cc07ec7d5a2cafb6c3f8deade35eb03a6aac44d1d92b256be9250e17a57104",
+            "90c036fcb846bb89b62865da192af8a4e7484b2041986883d74fe24ed7a75456",
+            "500fa5d67a148269836eb93e6f5ad6017ab37a6facc5f04e6a20cf08625a5904",
+            "a64e6fc21606c707378f57272881a4802ac8cdd849e5baa86508c1992ac8fd10",
+            "7294f3b8611cba0b4ec02f7b726b8b2e64edf6dc7f080bf1cd0070d0863ba71f",
+            "ec4e5dd22d202e0a291963590a763ec60a86d1b928ae524ac5407c7dcfcefa17",
+            "ae9e95b4a7d96acd97363b89c3a2e7a00ab082b7250f87ea2ed61dfd2c8f4564",
+            "8a29562a616b7ad26a331b8724a44b5415f0464052928cdbc8b52d3ade87d66a",
+            "10aad28c3af43fc24afd124823a233f4c78aaedb9a3e23c4d44104038be4735e",
+            "50f5f086578a2214bd7081451e077c8f27ac1a45ae3872a46802efbf0e47984b",
+            "ea32b3a24b8280942bfdda56f462607f3bbad58811cdd8abb6b488101416ea14",
+            "288e9fd2ea7cab6b9120f6e114effd19b9eac4411e2d9bdb26de1639c555f908",
+            "b20ab185f17783d76c694cef2928f2194c739b4c50d3e8f5ff4abea12bc6c60b",
+        )).unwrap();
+        assert_eq!(proof.to_bytes(), expected);
+
+        assert!(
+            proof
+                .verify_single_with_rng(
+                    &bp_gens,
+                    &pc_gens,
+                    &mut Transcript::new(b"BackendTest"),
+                    &V,
+                    64,
+                    &mut util::deterministic_rng([3u8; 32]),
+                ).is_ok()
+        );
+    }
+
+    #[test]
+    fn interval_proofs() {
+        let mut rng = util::deterministic_rng([17u8; 32]);
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(128, 2);
+        let blinding = Scalar::random(&mut rng);
+        let (min, max) = (1000u128, 50000u128);
+
+        let prove = |v: u128, min: u128, max: u128| {
+            let mut rng = util::deterministic_rng([48u8; 32]);
+            RangeProof::prove_interval_with_rng(
+                &bp_gens,
+                &pc_gens,
+                &mut Transcript::new(b"IntervalTest"),
+                v,
+                &blinding,
+                min,
+                max,
+                &mut rng,
+            )
+        };
+        let verify = |proof: &RangeProof, V: &CompressedRistretto, min: u128, max: u128| {
+            let mut rng = util::deterministic_rng([49u8; 32]);
+            proof.verify_interval_with_rng(
+                &bp_gens,
+                &pc_gens,
+                &mut Transcript::new(b"IntervalTest"),
+                V,
+                min,
+                max,
+                &mut rng,
+            )
+        };
+        let commit = |v: u128| pc_gens.commit(util::scalar_from_u128(v), blinding).compress();
+
+        // The bounds themselves are in the interval, and the proofs
+        // verify against the ordinary commitments to the values.
+        for &v in [min, 20000, max].iter() {
+            let (proof, V) = prove(v, min, max).unwrap();
+            assert_eq!(V, commit(v));
+            assert!(verify(&proof, &V, min, max).is_ok());
+        }
+
+        // Values just outside the interval can't be proven...
+        assert_eq!(prove(min - 1, min, max).unwrap_err(), ProofError::InvalidInterval);
+        assert_eq!(prove(max + 1, min, max).unwrap_err(), ProofError::InvalidInterval);
+        assert_eq!(prove(min, max, min).unwrap_err(), ProofError::InvalidInterval);
+
+        // ...and proofs for the bounds don't verify for them.
+        let (proof_min, _) = prove(min, min, max).unwrap();
+        assert!(verify(&proof_min, &commit(min - 1), min, max).is_err());
+        let (proof_max, _) = prove(max, min, max).unwrap();
+        assert!(verify(&proof_max, &commit(max + 1), min, max).is_err());
+
+        // The bounds are bound to the proof.
+        let (proof, V) = prove(20000, min, max).unwrap();
+        assert!(verify(&proof, &V, min, max + 1).is_err());
+        assert!(verify(&proof, &V, min - 1, max).is_err());
+
+        // Intervals as wide as the whole u64 range use 64-bit proofs.
+        let u64_max = u64::max_value() as u128;
+        let (proof, V) = prove(u64_max, 0, u64_max).unwrap();
+        assert!(verify(&proof, &V, 0, u64_max).is_ok());
+        assert_eq!(proof.to_bytes().len(), (2 * 7 + 9) * 32);
+
+        // Wider intervals, here above 2^64, use 128-bit proofs.
+        let (min, max) = (1u128 << 100, (1u128 << 100) + (1u128 << 64) + 5);
+        for &v in [min, min + (1u128 << 64), max].iter() {
+            let (proof, V) = prove(v, min, max).unwrap();
+            assert_eq!(V, commit(v));
+            assert!(verify(&proof, &V, min, max).is_ok());
+            assert_eq!(proof.to_bytes().len(), (2 * 8 + 9) * 32);
         }
+        assert_eq!(prove(max + 1, min, max).unwrap_err(), ProofError::InvalidInterval);
+        let (proof, V) = prove(max, min, max).unwrap();
+        assert!(verify(&proof, &commit(max + 1), min, max).is_err());
+        assert!(verify(&proof, &V, min, max + 1).is_err());
+
+        // So does the whole u128 range.
+        let (proof, V) = prove(u128::max_value(), 0, u128::max_value()).unwrap();
+        assert!(verify(&proof, &V, 0, u128::max_value()).is_ok());
     }
 
     #[test]
-    fn detect_dishonest_dealer_during_aggregation() {
+    fn interval_bitsize_covers_u128() {
+        assert_eq!(interval_bitsize(5, 5), 8);
+        assert_eq!(interval_bitsize(0, 255), 8);
+        assert_eq!(interval_bitsize(0, 256), 16);
+        assert_eq!(interval_bitsize(0, u64::max_value() as u128), 64);
+        assert_eq!(interval_bitsize(0, 1 << 64), 128);
+        assert_eq!(interval_bitsize(1 << 100, (1 << 100) + 1000), 16);
+        assert_eq!(interval_bitsize(0, u128::max_value()), 128);
+    }
+
+    /// Round-trips a message through its wire format.
+    fn bincode_roundtrip<T>(message: &T) -> T
+    where
+        T: Serialize + for<'de> Deserialize<'de>,
+    {
+        use bincode;
+        bincode::deserialize(&bincode::serialize(message).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn create_and_verify_with_serialized_messages() {
         use self::dealer::*;
         use self::party::*;
 
-        // Simulate one party
-        let m = 1;
+        let m = 4;
         let n = 32;
 
         let pc_gens = PedersenGens::default();
         let bp_gens = BulletproofGens::new(n, m);
 
         use rand::Rng;
-        let mut rng = rand::thread_rng();
+        let mut rng = util::deterministic_rng([18u8; 32]);
         let mut transcript = Transcript::new(b"AggregatedRangeProofTest");
 
-        let v0 = rng.gen::<u32>() as u64;
-        let v0_blinding = Scalar::random(&mut rng);
-        let party0 = Party::new(&bp_gens, &pc_gens, v0, v0_blinding, n).unwrap();
-
         let dealer = Dealer::new(&bp_gens, &pc_gens, &mut transcript, n, m).unwrap();
 
-        // Now do the protocol flow as normal....
+        let (parties, bit_commitments): (Vec<_>, Vec<_>) = (0..m)
+            .map(|j| {
+                let v = rng.gen::<u32>() as u128;
+                let party = Party::new(&bp_gens, &pc_gens, v, Scalar::random(&mut rng), n).unwrap();
+                let (party, bit_commitment) = party.assign_position_with_rng(j, &mut rng).unwrap();
+                (party, bincode_roundtrip(&bit_commitment))
+            }).unzip();
+        let value_commitments: Vec<_> = bit_commitments.iter().map(|c| c.V_j).collect();
 
-        let (party0, bit_com0) = party0.assign_position(0).unwrap();
+        let (dealer, bit_challenge) = dealer.receive_bit_commitments(bit_commitments).unwrap();
+        let bit_challenge = bincode_roundtrip(&bit_challenge);
 
-        let (dealer, bit_challenge) = dealer.receive_bit_commitments(vec![bit_com0]).unwrap();
+        let (parties, poly_commitments): (Vec<_>, Vec<_>) = parties
+            .into_iter()
+            .map(|p| {
+                let (p, poly_commitment) = p.apply_challenge_with_rng(&bit_challenge, &mut rng);
+                (p, bincode_roundtrip(&poly_commitment))
+            }).unzip();
 
-        let (party0, poly_com0) = party0.apply_challenge(&bit_challenge);
+        let (dealer, poly_challenge) = dealer.receive_poly_commitments(poly_commitments).unwrap();
+        let poly_challenge = bincode_roundtrip(&poly_challenge);
 
-        let (_dealer, mut poly_challenge) =
-            dealer.receive_poly_commitments(vec![poly_com0]).unwrap();
+        let proof_shares: Vec<_> = parties
+            .into_iter()
+            .map(|p| bincode_roundtrip(&p.apply_challenge(&poly_challenge).unwrap()))
+            .collect();
 
-        // But now simulate a malicious dealer choosing x = 0
-        poly_challenge.x = Scalar::zero();
+        let proof = dealer.receive_shares_with_rng(&proof_shares, &mut rng).unwrap();
 
-        let maybe_share0 = party0.apply_challenge(&poly_challenge);
+        let mut transcript = Transcript::new(b"AggregatedRangeProofTest");
+        assert!(
+            proof
+                .verify_multiple_with_rng(
+                    &bp_gens,
+                    &pc_gens,
+                    &mut transcript,
+                    &value_commitments,
+                    n,
+                    &mut rng
+                ).is_ok()
+        );
+    }
 
-        // XXX when we have error types, check finer info than "was error"
-        assert!(maybe_share0.is_err());
+    #[test]
+    fn create_and_verify_with_saved_states() {
+        use self::dealer::*;
+        use self::party::*;
+
+        // 8 + 16 bits are padded to 32 by one dealer party of 8 bits,
+        // so that the dealer also saves a padding party.
+        let bitsizes = [8, 16];
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(16, 3);
+
+        let mut rng = util::deterministic_rng([19u8; 32]);
+        let values = [200u128, 60000u128];
+
+        // Each round starts from a fresh copy of the initial transcript,
+        // as a stateless dealer would.
+        let initial_transcript = Transcript::new(b"SavedStatesTest");
+
+        let dealer = {
+            let mut transcript = initial_transcript.clone();
+            let dealer =
+                Dealer::new_with_bitsizes(&bp_gens, &pc_gens, &mut transcript, &bitsizes).unwrap();
+            bincode_roundtrip(&dealer.save())
+        };
+
+        let parties: Vec<_> = values
+            .iter()
+            .zip(bitsizes.iter())
+            .map(|(&v, &n)| {
+                let party = Party::new(&bp_gens, &pc_gens, v, Scalar::random(&mut rng), n).unwrap();
+                bincode_roundtrip(&party.save())
+            }).collect();
+
+        // Round 1: the parties commit to their bits.
+        let (parties, bit_commitments): (Vec<_>, Vec<_>) = parties
+            .into_iter()
+            .enumerate()
+            .map(|(j, p)| {
+                let offset: usize = bitsizes[..j].iter().sum();
+                let (p, bit_commitment) = p
+                    .resume(&bp_gens, &pc_gens)
+                    .unwrap()
+                    .assign_position_with_offset_and_rng(j, offset, &mut rng)
+                    .unwrap();
+                (bincode_roundtrip(&p.save()), bit_commitment)
+            }).unzip();
+        let value_commitments: Vec<_> = bit_commitments.iter().map(|c| c.V_j).collect();
+
+        let (dealer, bit_challenge) = {
+            let mut transcript = initial_transcript.clone();
+            let (dealer, bit_challenge) = dealer
+                .resume(&bp_gens, &pc_gens, &mut transcript)
+                .unwrap()
+                .receive_bit_commitments(bit_commitments)
+                .unwrap();
+            (bincode_roundtrip(&dealer.save()), bit_challenge)
+        };
+
+        // Round 2: the parties commit to their polynomials.
+        let (parties, poly_commitments): (Vec<_>, Vec<_>) = parties
+            .into_iter()
+            .map(|p| {
+                let (p, poly_commitment) =
+                    p.resume(&pc_gens).unwrap().apply_challenge_with_rng(&bit_challenge, &mut rng);
+                (bincode_roundtrip(&p), poly_commitment)
+            }).unzip();
+
+        let (dealer, poly_challenge) = {
+            let mut transcript = initial_transcript.clone();
+            let (dealer, poly_challenge) = dealer
+                .resume(&bp_gens, &pc_gens, &mut transcript)
+                .unwrap()
+                .receive_poly_commitments(poly_commitments)
+                .unwrap();
+            (bincode_roundtrip(&dealer.save()), poly_challenge)
+        };
+
+        // Round 3: the parties compute their proof shares.
+        let proof_shares: Vec<_> = parties
+            .into_iter()
+            .map(|p| p.apply_challenge(&poly_challenge).unwrap())
+            .collect();
+
+        // Resuming with a different transcript is detected.
+        let dealer: SavedDealerAwaitingProofShares = {
+            use bincode;
+            let bytes = bincode::serialize(&dealer).unwrap();
+            let mut wrong_transcript = Transcript::new(b"SomeOtherTranscript");
+            let wrong_dealer: SavedDealerAwaitingProofShares = bincode::deserialize(&bytes).unwrap();
+            assert!(
+                wrong_dealer
+                    .resume(&bp_gens, &pc_gens, &mut wrong_transcript)
+                    .is_err()
+            );
+            bincode::deserialize(&bytes).unwrap()
+        };
+
+        let proof = {
+            let mut transcript = initial_transcript.clone();
+            dealer
+                .resume(&bp_gens, &pc_gens, &mut transcript)
+                .unwrap()
+                .receive_shares_with_rng(&proof_shares, &mut rng)
+                .unwrap()
+        };
+
+        let mut transcript = initial_transcript.clone();
+        assert!(
+            proof
+                .verify_multiple_with_bitsizes_and_rng(
+                    &bp_gens,
+                    &pc_gens,
+                    &mut transcript,
+                    &value_commitments,
+                    &bitsizes,
+                    &mut rng
+                ).is_ok()
+        );
+    }
+
+    #[test]
+    fn restart_after_party_dropout() {
+        use self::dealer::*;
+        use self::party::*;
+
+        let m = 4;
+        let n = 32;
+
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(n, m);
+
+        use rand::Rng;
+        let mut rng = util::deterministic_rng([20u8; 32]);
+        let mut transcript = Transcript::new(b"PartyDropoutTest");
+
+        let values: Vec<u128> = (0..m).map(|_| rng.gen::<u32>() as u128).collect();
+        let blindings: Vec<Scalar> = (0..m).map(|_| Scalar::random(&mut rng)).collect();
+
+        let dealer = Dealer::new(&bp_gens, &pc_gens, &mut transcript, n, m).unwrap();
+
+        let (parties, bit_commitments): (Vec<_>, Vec<_>) = (0..m)
+            .map(|j| {
+                Party::new(&bp_gens, &pc_gens, values[j], blindings[j], n)
+                    .unwrap()
+                    .assign_position_with_rng(j, &mut rng)
+                    .unwrap()
+            }).unzip();
+
+        let (dealer, _bit_challenge) = dealer.receive_bit_commitments(bit_commitments).unwrap();
+
+        // Party 2 never sends its polynomial commitment, so the dealer
+        // restarts without it, and the other parties start over.
+        drop(parties);
+        let dealer = dealer.abort_party(2).unwrap();
+
+        let remaining = [0, 1, 3];
+        let (parties, bit_commitments): (Vec<_>, Vec<_>) = remaining
+            .iter()
+            .enumerate()
+            .map(|(j, &k)| {
+                Party::new(&bp_gens, &pc_gens, values[k], blindings[k], n)
+                    .unwrap()
+                    .assign_position_with_offset_and_rng(j, j * n, &mut rng)
+                    .unwrap()
+            }).unzip();
+        let value_commitments: Vec<_> = bit_commitments.iter().map(|c| c.V_j).collect();
+
+        let (dealer, bit_challenge) = dealer.receive_bit_commitments(bit_commitments).unwrap();
+
+        let (parties, poly_commitments): (Vec<_>, Vec<_>) = parties
+            .into_iter()
+            .map(|p| p.apply_challenge_with_rng(&bit_challenge, &mut rng))
+            .unzip();
+
+        let (dealer, poly_challenge) = dealer.receive_poly_commitments(poly_commitments).unwrap();
+
+        let proof_shares: Vec<_> = parties
+            .into_iter()
+            .map(|p| p.apply_challenge(&poly_challenge).unwrap())
+            .collect();
+
+        let proof = dealer.receive_shares_with_rng(&proof_shares, &mut rng).unwrap();
+
+        let mut transcript = Transcript::new(b"PartyDropoutTest");
+        assert!(
+            proof
+                .verify_multiple_with_bitsizes_and_rng(
+                    &bp_gens,
+                    &pc_gens,
+                    &mut transcript,
+                    &value_commitments,
+                    &[n, n, n],
+                    &mut rng
+                ).is_ok()
+        );
+    }
+
+    /// Runs the protocol for the parties at `positions`, returning
+    /// the dealer awaiting their proof shares, and the shares.
+    fn dropout_run<'a, 'b>(
+        dealer: dealer::DealerAwaitingBitCommitments<'a, 'b>,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        values: &[u128],
+        blindings: &[Scalar],
+        n: usize,
+        positions: &[usize],
+    ) -> (dealer::DealerAwaitingProofShares<'a, 'b>, Vec<messages::ProofShare>) {
+        use self::party::*;
+
+        let mut rng = util::deterministic_rng([21u8; 32]);
+        let (parties, bit_commitments): (Vec<_>, Vec<_>) = positions
+            .iter()
+            .enumerate()
+            .map(|(j, &k)| {
+                Party::new(bp_gens, pc_gens, values[k], blindings[k], n)
+                    .unwrap()
+                    .assign_position_with_offset_and_rng(j, j * n, &mut rng)
+                    .unwrap()
+            }).unzip();
+        let (dealer, bit_challenge) = dealer.receive_bit_commitments(bit_commitments).unwrap();
+        let (parties, poly_commitments): (Vec<_>, Vec<_>) = parties
+            .into_iter()
+            .map(|p| p.apply_challenge_with_rng(&bit_challenge, &mut rng))
+            .unzip();
+        let (dealer, poly_challenge) = dealer.receive_poly_commitments(poly_commitments).unwrap();
+        let proof_shares: Vec<_> = parties
+            .into_iter()
+            .map(|p| p.apply_challenge(&poly_challenge).unwrap())
+            .collect();
+        (dealer, proof_shares)
+    }
+
+    /// Starts a dealer for `values.len()` parties with 16-bit values,
+    /// returning it with the parties and their bit commitments.
+    fn start_parties<'a, 'b>(
+        bp_gens: &'b BulletproofGens,
+        pc_gens: &'b PedersenGens,
+        transcript: &'a mut Transcript,
+        values: &[u128],
+        blindings: &[Scalar],
+    ) -> (
+        dealer::DealerAwaitingBitCommitments<'a, 'b>,
+        Vec<party::PartyAwaitingBitChallenge<'b>>,
+        Vec<messages::BitCommitment>,
+    ) {
+        use self::dealer::*;
+        use self::party::*;
+
+        let mut rng = util::deterministic_rng([39u8; 32]);
+        let dealer = Dealer::new(bp_gens, pc_gens, transcript, 16, values.len()).unwrap();
+        let (parties, bit_commitments): (Vec<_>, Vec<_>) = (0..values.len())
+            .map(|j| {
+                Party::new(bp_gens, pc_gens, values[j], blindings[j], 16)
+                    .unwrap()
+                    .assign_position_with_rng(j, &mut rng)
+                    .unwrap()
+            }).unzip();
+        (dealer, parties, bit_commitments)
+    }
+
+    #[test]
+    fn dropout_at_each_round() {
+        use self::dealer::*;
+        use self::messages::ProofShare;
+        use self::party::*;
+
+        let m = 4;
+        let n = 16;
+
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(n, m);
+
+        use rand::Rng;
+        let mut rng = util::deterministic_rng([22u8; 32]);
+        let values: Vec<u128> = (0..m).map(|_| rng.gen::<u16>() as u128).collect();
+        let blindings: Vec<Scalar> = (0..m).map(|_| Scalar::random(&mut rng)).collect();
+
+        // Checks that the parties at `positions` can finish, and that
+        // their value commitments are unchanged.
+        let finish = |dealer, positions: &[usize]| {
+            let mut rng = util::deterministic_rng([52u8; 32]);
+            let (dealer, proof_shares) =
+                dropout_run(dealer, &bp_gens, &pc_gens, &values, &blindings, n, positions);
+            let proof = dealer.receive_shares_with_rng(&proof_shares, &mut rng).unwrap();
+            let value_commitments: Vec<_> = positions
+                .iter()
+                .map(|&k| {
+                    pc_gens
+                        .commit(util::scalar_from_u128(values[k]), blindings[k])
+                        .compress()
+                }).collect();
+            let mut transcript = Transcript::new(b"DropoutTest");
+            assert!(
+                proof
+                    .verify_multiple_with_bitsizes_and_rng(
+                        &bp_gens,
+                        &pc_gens,
+                        &mut transcript,
+                        &value_commitments,
+                        &vec![n; positions.len()],
+                        &mut rng,
+                    ).is_ok()
+            );
+        };
+
+        // Parties 0 and 2 drop out before sending their bit commitments.
+        let mut transcript = Transcript::new(b"DropoutTest");
+        let dealer = Dealer::new(&bp_gens, &pc_gens, &mut transcript, n, m).unwrap();
+        finish(dealer.abort_parties(&[0, 2]).unwrap(), &[1, 3]);
+
+        // Parties 1 and 3 drop out before sending their poly commitments.
+        let mut transcript = Transcript::new(b"DropoutTest");
+        let dealer = Dealer::new(&bp_gens, &pc_gens, &mut transcript, n, m).unwrap();
+        let bit_commitments: Vec<_> = (0..m)
+            .map(|j| {
+                Party::new(&bp_gens, &pc_gens, values[j], blindings[j], n)
+                    .unwrap()
+                    .assign_position_with_rng(j, &mut rng)
+                    .unwrap()
+                    .1
+            }).collect();
+        let (dealer, _) = dealer.receive_bit_commitments(bit_commitments).unwrap();
+        finish(dealer.abort_parties(&[3, 1]).unwrap(), &[0, 2]);
+
+        // Party 1 drops out before sending its proof share.
+        let mut transcript = Transcript::new(b"DropoutTest");
+        let dealer = Dealer::new(&bp_gens, &pc_gens, &mut transcript, n, m).unwrap();
+        let (dealer, proof_shares) =
+            dropout_run(dealer, &bp_gens, &pc_gens, &values, &blindings, n, &[0, 1, 2, 3]);
+        let arrived: Vec<Option<ProofShare>> = proof_shares
+            .into_iter()
+            .enumerate()
+            .map(|(j, share)| if j == 1 { None } else { Some(share) })
+            .collect();
+        let positions = match dealer.collect_shares(&arrived) {
+            Err(MPCError::PartyDropout { positions }) => positions,
+            _ => panic!("The missing share was not detected"),
+        };
+        assert_eq!(positions, vec![1]);
+        finish(dealer.abort_parties(&positions).unwrap(), &[0, 2, 3]);
+
+        // Dropping every party leaves nothing to aggregate.
+        let mut transcript = Transcript::new(b"DropoutTest");
+        let dealer = Dealer::new(&bp_gens, &pc_gens, &mut transcript, n, m).unwrap();
+        assert_eq!(
+            dealer.abort_parties(&[0, 1, 2, 3]).err(),
+            Some(MPCError::NoPartiesRemain)
+        );
+
+        // Positions past the last party are reported, and so is an
+        // empty list of positions.
+        let mut transcript = Transcript::new(b"DropoutTest");
+        let dealer = Dealer::new(&bp_gens, &pc_gens, &mut transcript, n, m).unwrap();
+        assert_eq!(
+            dealer.abort_parties(&[1, 4]).err(),
+            Some(MPCError::PositionOutOfRange {
+                position: 4,
+                num_parties: 4
+            })
+        );
+        let mut transcript = Transcript::new(b"DropoutTest");
+        let dealer = Dealer::new(&bp_gens, &pc_gens, &mut transcript, n, m).unwrap();
+        assert_eq!(
+            dealer.abort_parties(&[]).err(),
+            Some(MPCError::InvalidAggregation {
+                got: 0,
+                reason: "the positions to abort must be nonempty"
+            })
+        );
+    }
+
+    #[test]
+    fn streaming_dealer_restarts_after_dropout() {
+        use self::dealer::*;
+        use self::party::*;
+
+        let m = 4;
+        let n = 16;
+
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(n, m);
+
+        use rand::Rng;
+        let mut rng = util::deterministic_rng([23u8; 32]);
+        let values: Vec<u128> = (0..m).map(|_| rng.gen::<u16>() as u128).collect();
+        let blindings: Vec<Scalar> = (0..m).map(|_| Scalar::random(&mut rng)).collect();
+
+        let mut transcript = Transcript::new(b"StreamingDropoutTest");
+        let mut dealer = Dealer::new(&bp_gens, &pc_gens, &mut transcript, n, m)
+            .unwrap()
+            .streaming();
+
+        let (parties, bit_commitments): (Vec<_>, Vec<_>) = (0..m)
+            .map(|j| {
+                Party::new(&bp_gens, &pc_gens, values[j], blindings[j], n)
+                    .unwrap()
+                    .assign_position_with_rng(j, &mut rng)
+                    .unwrap()
+            }).unzip();
+        for (j, bit_commitment) in bit_commitments.into_iter().enumerate() {
+            dealer.absorb_bit_commitment(j, bit_commitment).unwrap();
+        }
+        let (mut dealer, bit_challenge) = dealer.finish().unwrap();
+
+        // Party 2 never sends its polynomial commitment
+        let poly_commitments: Vec<_> = parties
+            .into_iter()
+            .map(|p| p.apply_challenge_with_rng(&bit_challenge, &mut rng).1)
+            .collect();
+        dealer
+            .absorb_poly_commitment(0, poly_commitments[0])
+            .unwrap();
+        dealer
+            .absorb_poly_commitment(1, poly_commitments[1])
+            .unwrap();
+        let dealer = dealer.abort_party(2).unwrap();
+
+        let positions = [0, 1, 3];
+        let (dealer, proof_shares) =
+            dropout_run(dealer, &bp_gens, &pc_gens, &values, &blindings, n, &positions);
+        let proof = dealer.receive_shares_with_rng(&proof_shares, &mut rng).unwrap();
+
+        let value_commitments: Vec<_> = positions
+            .iter()
+            .map(|&k| {
+                pc_gens
+                    .commit(util::scalar_from_u128(values[k]), blindings[k])
+                    .compress()
+            }).collect();
+        let mut transcript = Transcript::new(b"StreamingDropoutTest");
+        assert!(
+            proof
+                .verify_multiple_with_bitsizes_and_rng(
+                    &bp_gens,
+                    &pc_gens,
+                    &mut transcript,
+                    &value_commitments,
+                    &[n, n, n],
+                    &mut rng,
+                ).is_ok()
+        );
+    }
+
+    #[test]
+    fn abort_last_party() {
+        use self::dealer::*;
+        use errors::MPCError;
+
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 1);
+        let mut transcript = Transcript::new(b"PartyDropoutTest");
+
+        let dealer = Dealer::new(&bp_gens, &pc_gens, &mut transcript, 32, 1).unwrap();
+        assert_eq!(dealer.abort_party(0).err(), Some(MPCError::NoPartiesRemain));
+
+        let mut transcript = Transcript::new(b"PartyDropoutTest");
+        let dealer = Dealer::new(&bp_gens, &pc_gens, &mut transcript, 32, 1).unwrap();
+        assert_eq!(
+            dealer.abort_party(1).err(),
+            Some(MPCError::PositionOutOfRange {
+                position: 1,
+                num_parties: 1
+            })
+        );
+    }
+
+    #[test]
+    fn reject_noncanonical_scalars_in_messages() {
+        use self::messages::*;
+        use bincode;
+
+        let mut rng = util::deterministic_rng([24u8; 32]);
+        let bit_challenge = BitChallenge {
+            y: Scalar::random(&mut rng),
+            z: Scalar::random(&mut rng),
+        };
+        let mut bytes = bincode::serialize(&bit_challenge).unwrap();
+
+        // Replace the encoding of y with an unreduced scalar.
+        let y_bytes = bit_challenge.y.to_bytes();
+        let pos = bytes
+            .windows(32)
+            .position(|w| w == &y_bytes[..])
+            .unwrap();
+        for b in bytes[pos..pos + 32].iter_mut() {
+            *b = 0xff;
+        }
+
+        assert!(bincode::deserialize::<BitChallenge>(&bytes).is_err());
+    }
+
+    #[test]
+    fn detect_dishonest_party_during_aggregation() {
+        use self::dealer::*;
+        use self::party::*;
+
+        use errors::MPCError;
+
+        // Simulate four parties, two of which will be dishonest and use a 64-bit value.
+        let m = 4;
+        let n = 32;
+
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(n, m);
+
+        use rand::Rng;
+        let mut rng = util::deterministic_rng([25u8; 32]);
+        let mut transcript = Transcript::new(b"AggregatedRangeProofTest");
+
+        // Parties 0, 2 are honest and use a 32-bit value
+        let v0 = rng.gen::<u32>() as u128;
+        let v0_blinding = Scalar::random(&mut rng);
+        let party0 = Party::new(&bp_gens, &pc_gens, v0, v0_blinding, n).unwrap();
+
+        let v2 = rng.gen::<u32>() as u128;
+        let v2_blinding = Scalar::random(&mut rng);
+        let party2 = Party::new(&bp_gens, &pc_gens, v2, v2_blinding, n).unwrap();
+
+        // Parties 1, 3 are dishonest and use a 64-bit value
+        let v1 = rng.gen::<u64>() as u128;
+        let v1_blinding = Scalar::random(&mut rng);
+        let party1 = Party::new(&bp_gens, &pc_gens, v1, v1_blinding, n).unwrap();
+
+        let v3 = rng.gen::<u64>() as u128;
+        let v3_blinding = Scalar::random(&mut rng);
+        let party3 = Party::new(&bp_gens, &pc_gens, v3, v3_blinding, n).unwrap();
+
+        let dealer = Dealer::new(&bp_gens, &pc_gens, &mut transcript, n, m).unwrap();
+
+        let (party0, bit_com0) = party0.assign_position_with_rng(0, &mut rng).unwrap();
+        let (party1, bit_com1) = party1.assign_position_with_rng(1, &mut rng).unwrap();
+        let (party2, bit_com2) = party2.assign_position_with_rng(2, &mut rng).unwrap();
+        let (party3, bit_com3) = party3.assign_position_with_rng(3, &mut rng).unwrap();
+
+        let (dealer, bit_challenge) = dealer
+            .receive_bit_commitments(vec![bit_com0, bit_com1, bit_com2, bit_com3])
+            .unwrap();
+
+        let (party0, poly_com0) = party0.apply_challenge_with_rng(&bit_challenge, &mut rng);
+        let (party1, poly_com1) = party1.apply_challenge_with_rng(&bit_challenge, &mut rng);
+        let (party2, poly_com2) = party2.apply_challenge_with_rng(&bit_challenge, &mut rng);
+        let (party3, poly_com3) = party3.apply_challenge_with_rng(&bit_challenge, &mut rng);
+
+        let (dealer, poly_challenge) = dealer
+            .receive_poly_commitments(vec![poly_com0, poly_com1, poly_com2, poly_com3])
+            .unwrap();
+
+        let share0 = party0.apply_challenge(&poly_challenge).unwrap();
+        let share1 = party1.apply_challenge(&poly_challenge).unwrap();
+        let share2 = party2.apply_challenge(&poly_challenge).unwrap();
+        let share3 = party3.apply_challenge(&poly_challenge).unwrap();
+
+        match dealer.receive_shares_with_rng(&[share0, share1, share2, share3], &mut rng) {
+            Err(MPCError::MalformedProofShares { bad_shares }) => {
+                assert_eq!(bad_shares, vec![1, 3]);
+            }
+            Err(_) => {
+                panic!("Got wrong error type from malformed shares");
+            }
+            Ok(_) => {
+                panic!("The proof was malformed, but it was not detected");
+            }
+        }
+    }
+
+    #[test]
+    fn detect_dishonest_dealer_during_aggregation() {
+        use self::dealer::*;
+        use self::party::*;
+
+        // Simulate one party
+        let m = 1;
+        let n = 32;
+
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(n, m);
+
+        use rand::Rng;
+        let mut rng = util::deterministic_rng([26u8; 32]);
+        let mut transcript = Transcript::new(b"AggregatedRangeProofTest");
+
+        let v0 = rng.gen::<u32>() as u128;
+        let v0_blinding = Scalar::random(&mut rng);
+        let party0 = Party::new(&bp_gens, &pc_gens, v0, v0_blinding, n).unwrap();
+
+        let dealer = Dealer::new(&bp_gens, &pc_gens, &mut transcript, n, m).unwrap();
+
+        // Now do the protocol flow as normal....
+
+        let (party0, bit_com0) = party0.assign_position_with_rng(0, &mut rng).unwrap();
+
+        let (dealer, bit_challenge) = dealer.receive_bit_commitments(vec![bit_com0]).unwrap();
+
+        let (party0, poly_com0) = party0.apply_challenge_with_rng(&bit_challenge, &mut rng);
+
+        let (_dealer, mut poly_challenge) =
+            dealer.receive_poly_commitments(vec![poly_com0]).unwrap();
+
+        // But now simulate a malicious dealer choosing x = 0
+        poly_challenge.x = Scalar::zero();
+
+        let maybe_share0 = party0.apply_challenge(&poly_challenge);
+
+        // XXX when we have error types, check finer info than "was error"
+        assert!(maybe_share0.is_err());
+    }
+
+    #[test]
+    fn proof_errors_are_granular() {
+        let mut rng = util::deterministic_rng([27u8; 32]);
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 2);
+        let blinding = Scalar::from(7u64);
+
+        let prove = |values: &[u128], blindings: &[Scalar], n: usize| {
+            let mut rng = util::deterministic_rng([50u8; 32]);
+            let mut transcript = Transcript::new(b"ErrorTest");
+            RangeProof::prove_multiple_with_rng(
+                &bp_gens,
+                &pc_gens,
+                &mut transcript,
+                values,
+                blindings,
+                n,
+                &mut rng,
+            )
+        };
+        let verify = |proof: &RangeProof, Vs: &[CompressedRistretto], n: usize| {
+            let mut rng = util::deterministic_rng([51u8; 32]);
+            let mut transcript = Transcript::new(b"ErrorTest");
+            proof.verify_multiple_with_rng(&bp_gens, &pc_gens, &mut transcript, Vs, n, &mut rng)
+        };
+
+        assert_eq!(
+            prove(&[1, 2], &[blinding], 32).err(),
+            Some(ProofError::WrongNumBlindingFactors {
+                expected: 2,
+                actual: 1
+            })
+        );
+        assert_eq!(
+            prove(&[1], &[blinding], 10).err(),
+            Some(ProofError::InvalidBitsize {
+                got: 10,
+                allowed: ALLOWED_BITSIZES
+            })
+        );
+        assert_eq!(
+            prove(&[1], &[blinding], 64).err(),
+            Some(ProofError::InsufficientGeneratorCapacity {
+                needed: 64,
+                available: 32
+            })
+        );
+        assert_eq!(
+            prove(&[1, 2, 3, 4], &[blinding; 4], 32).err(),
+            Some(ProofError::InsufficientPartyCapacity {
+                needed: 4,
+                available: 2
+            })
+        );
+        let mut transcript = Transcript::new(b"ErrorTest");
+        assert_eq!(
+            RangeProof::prove_multiple_with_rng(
+                &BulletproofGens::new(8, 4),
+                &pc_gens,
+                &mut transcript,
+                &[1, 2, 3],
+                &[blinding; 3],
+                8,
+                &mut rng
+            ).err(),
+            Some(ProofError::InvalidAggregation {
+                got: 3,
+                reason: "the number of parties must be a power of two"
+            })
+        );
+
+        let (proof, Vs) = prove(&[1, 2], &[blinding; 2], 16).unwrap();
+        assert!(verify(&proof, &Vs, 16).is_ok());
+
+        // A proof for a different size than the statement: 2 parties
+        // with 16 bits need 5 rounds, and with 32 bits 6.
+        assert_eq!(
+            verify(&proof, &Vs, 32).err(),
+            Some(ProofError::WrongNumRounds {
+                expected: 6,
+                actual: 5
+            })
+        );
+
+        // An invalid point encoding is reported by name
+        let mut bad_proof = proof.clone();
+        bad_proof.A = CompressedRistretto([0xff; 32]);
+        assert_eq!(
+            verify(&bad_proof, &Vs, 16).err(),
+            Some(ProofError::PointDecompressionError { which: "A" })
+        );
+        let bad_Vs = vec![Vs[0], CompressedRistretto([0xff; 32])];
+        assert_eq!(
+            verify(&proof, &bad_Vs, 16).err(),
+            Some(ProofError::PointDecompressionError { which: "V" })
+        );
+
+        // A well-formed but invalid proof fails the final check
+        let mut bad_proof = proof.clone();
+        bad_proof.t_x += Scalar::one();
+        assert_eq!(
+            verify(&bad_proof, &Vs, 16).err(),
+            Some(ProofError::VerificationError)
+        );
+
+        // Truncated bytes fail to parse: the proof is 2*5 + 9 elements
+        let bytes = proof.to_bytes();
+        assert_eq!(bytes.len(), 19 * 32);
+        assert_eq!(
+            RangeProof::from_bytes(&bytes[..bytes.len() - 1]).err(),
+            Some(ProofError::WrongProofLength {
+                expected: 19 * 32,
+                actual: 19 * 32 - 1
+            })
+        );
+        assert_eq!(
+            RangeProof::from_bytes(&bytes[..8 * 32]).err(),
+            Some(ProofError::WrongProofLength {
+                expected: 9 * 32,
+                actual: 8 * 32
+            })
+        );
+        // A proof can't have 32 rounds
+        assert_eq!(
+            RangeProof::from_bytes(&vec![0u8; (2 * 32 + 9) * 32]).err(),
+            Some(ProofError::WrongNumRounds {
+                expected: 31,
+                actual: 32
+            })
+        );
+
+        let mut transcript = Transcript::new(b"ErrorTest");
+        assert_eq!(
+            proof
+                .verify_multiple_with_bitsizes_and_rng(
+                    &bp_gens,
+                    &pc_gens,
+                    &mut transcript,
+                    &Vs,
+                    &[16],
+                    &mut rng
+                ).err(),
+            Some(ProofError::WrongNumBitsizes {
+                expected: 2,
+                actual: 1
+            })
+        );
+    }
+
+    #[test]
+    fn party_states_clear_secrets_on_drop() {
+        use self::party::*;
+        use core::mem::needs_drop;
+
+        // The party states and polynomial buffers would be plain
+        // data without their Drop impls clearing the secrets.
+        assert!(needs_drop::<PartyAwaitingPosition>());
+        assert!(needs_drop::<PartyAwaitingBitChallenge>());
+        assert!(needs_drop::<PartyAwaitingPolyChallenge>());
+        assert!(needs_drop::<SavedPartyAwaitingPosition>());
+        assert!(needs_drop::<util::Poly2>());
+    }
+
+    #[test]
+    fn dealer_rejects_malformed_bit_commitment_early() {
+        use self::dealer::*;
+        use self::party::*;
+        use errors::MPCError;
+
+        let mut rng = util::deterministic_rng([28u8; 32]);
+        let m = 2;
+        let n = 16;
+
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(n, m);
+        let mut transcript = Transcript::new(b"EarlyVerifyTest");
+
+        let dealer = Dealer::new(&bp_gens, &pc_gens, &mut transcript, n, m).unwrap();
+
+        let (_parties, mut bit_commitments): (Vec<_>, Vec<_>) = (0..m)
+            .map(|j| {
+                Party::new(&bp_gens, &pc_gens, j as u128, Scalar::from(j as u64 + 1), n)
+                    .unwrap()
+                    .assign_position_with_rng(j, &mut rng)
+                    .unwrap()
+            }).unzip();
+
+        // Party 1 sends a value commitment which is not a valid point
+        bit_commitments[1].V_j = CompressedRistretto([0xff; 32]);
+
+        assert!(dealer.verify_bit_commitment(0, &bit_commitments[0]).is_ok());
+        assert_eq!(
+            dealer.verify_bit_commitment(1, &bit_commitments[1]),
+            Err(MPCError::InvalidPoint { party: 1, field: "V" })
+        );
+        assert_eq!(
+            dealer.verify_bit_commitment(2, &bit_commitments[0]),
+            Err(MPCError::PositionOutOfRange {
+                position: 2,
+                num_parties: 2
+            })
+        );
+        assert_eq!(
+            dealer.verify_bit_commitment(7, &bit_commitments[0]),
+            Err(MPCError::PositionOutOfRange {
+                position: 7,
+                num_parties: 2
+            })
+        );
+        assert_eq!(
+            dealer.receive_bit_commitments(bit_commitments).err(),
+            Some(MPCError::InvalidPoint { party: 1, field: "V" })
+        );
+    }
+
+    #[test]
+    fn verify_with_extended_gens() {
+        let mut rng = util::deterministic_rng([29u8; 32]);
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 2);
+        let blindings = [Scalar::from(3u64), Scalar::from(4u64)];
+
+        let mut transcript = Transcript::new(b"ExtendedGensTest");
+        let (proof, value_commitments) = RangeProof::prove_multiple_with_rng(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &[17, 42],
+            &blindings,
+            32,
+            &mut rng,
+        ).unwrap();
+
+        let bp_gens = bp_gens.extend(64, 8);
+
+        let mut transcript = Transcript::new(b"ExtendedGensTest");
+        assert!(
+            proof
+                .verify_multiple_with_rng(
+                    &bp_gens,
+                    &pc_gens,
+                    &mut transcript,
+                    &value_commitments,
+                    32,
+                    &mut rng
+                ).is_ok()
+        );
+    }
+
+    #[test]
+    fn aggregate_locally_reports_bad_parties() {
+        let mut rng = util::deterministic_rng([30u8; 32]);
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(16, 4);
+        let blindings: Vec<Scalar> = (0..4).map(|i| Scalar::from(i as u64 + 1)).collect();
+
+        let mut transcript = Transcript::new(b"AggregateLocallyTest");
+        let (proof, value_commitments) = aggregate_locally_with_rng(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &[0, 1, 1000, 65535],
+            &blindings,
+            16,
+            &mut rng,
+        ).unwrap();
+
+        let mut transcript = Transcript::new(b"AggregateLocallyTest");
+        assert!(
+            proof
+                .verify_multiple_with_rng(
+                    &bp_gens,
+                    &pc_gens,
+                    &mut transcript,
+                    &value_commitments,
+                    16,
+                    &mut rng
+                ).is_ok()
+        );
+
+        // Parties 1 and 3 hold values which don't fit in 16 bits
+        let mut transcript = Transcript::new(b"AggregateLocallyTest");
+        match aggregate_locally_with_rng(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &[0, 1 << 16, 1000, 1 << 20],
+            &blindings,
+            16,
+            &mut rng,
+        ) {
+            Err(MPCError::MalformedProofShares { bad_shares }) => {
+                assert_eq!(bad_shares, vec![1, 3])
+            }
+            _ => panic!("The out-of-range values were not detected"),
+        }
+
+        let mut transcript = Transcript::new(b"AggregateLocallyTest");
+        assert_eq!(
+            aggregate_locally_with_rng(
+                &bp_gens,
+                &pc_gens,
+                &mut transcript,
+                &[0, 1],
+                &blindings,
+                16,
+                &mut rng
+            ).err(),
+            Some(MPCError::WrongNumBlindingFactors {
+                expected: 2,
+                actual: 4
+            })
+        );
+    }
+
+    #[test]
+    fn streaming_dealer_matches_batch_dealer() {
+        use self::dealer::*;
+        use self::party::*;
+
+        let m = 256;
+        let n = 8;
+
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(n, m);
+
+        use rand::Rng;
+        let mut rng = util::deterministic_rng([31u8; 32]);
+
+        let mut batch_transcript = Transcript::new(b"StreamingDealerTest");
+        let mut stream_transcript = Transcript::new(b"StreamingDealerTest");
+        let batch_dealer = Dealer::new(&bp_gens, &pc_gens, &mut batch_transcript, n, m).unwrap();
+        let mut stream_dealer = Dealer::new(&bp_gens, &pc_gens, &mut stream_transcript, n, m)
+            .unwrap()
+            .streaming();
+
+        let (parties, bit_commitments): (Vec<_>, Vec<_>) = (0..m)
+            .map(|j| {
+                let v = rng.gen::<u8>() as u128;
+                Party::new(&bp_gens, &pc_gens, v, Scalar::random(&mut rng), n)
+                    .unwrap()
+                    .assign_position_with_rng(j, &mut rng)
+                    .unwrap()
+            }).unzip();
+        let value_commitments: Vec<_> = bit_commitments.iter().map(|c| c.V_j).collect();
+
+        // Out-of-order and duplicate messages are rejected
+        assert_eq!(
+            stream_dealer.absorb_bit_commitment(1, bit_commitments[1]),
+            Err(MPCError::UnexpectedPosition {
+                expected: 0,
+                actual: 1
+            })
+        );
+        stream_dealer
+            .absorb_bit_commitment(0, bit_commitments[0])
+            .unwrap();
+        assert_eq!(
+            stream_dealer.absorb_bit_commitment(0, bit_commitments[0]),
+            Err(MPCError::UnexpectedPosition {
+                expected: 1,
+                actual: 0
+            })
+        );
+        for j in 1..m {
+            stream_dealer
+                .absorb_bit_commitment(j, bit_commitments[j])
+                .unwrap();
+        }
+
+        let (batch_dealer, bit_challenge) = batch_dealer
+            .receive_bit_commitments(bit_commitments)
+            .unwrap();
+        let (mut stream_dealer, stream_bit_challenge) = stream_dealer.finish().unwrap();
+        assert_eq!(bit_challenge.y, stream_bit_challenge.y);
+        assert_eq!(bit_challenge.z, stream_bit_challenge.z);
+
+        let (parties, poly_commitments): (Vec<_>, Vec<_>) = parties
+            .into_iter()
+            .map(|p| p.apply_challenge_with_rng(&bit_challenge, &mut rng))
+            .unzip();
+
+        for (j, poly_commitment) in poly_commitments.iter().enumerate() {
+            stream_dealer
+                .absorb_poly_commitment(j, *poly_commitment)
+                .unwrap();
+        }
+
+        let (batch_dealer, poly_challenge) = batch_dealer
+            .receive_poly_commitments(poly_commitments)
+            .unwrap();
+        let (mut stream_dealer, stream_poly_challenge) = stream_dealer.finish().unwrap();
+        assert_eq!(poly_challenge.x, stream_poly_challenge.x);
+
+        let proof_shares: Vec<_> = parties
+            .into_iter()
+            .map(|p| p.apply_challenge(&poly_challenge).unwrap())
+            .collect();
+
+        // A malformed share is rejected with its position
+        let mut bad_share = proof_shares[1].clone();
+        bad_share.t_x += Scalar::one();
+        stream_dealer
+            .absorb_proof_share(0, proof_shares[0].clone())
+            .unwrap();
+        assert_eq!(
+            stream_dealer.absorb_proof_share(1, bad_share),
+            Err(MPCError::MalformedProofShares {
+                bad_shares: vec![1]
+            })
+        );
+        for j in 1..m {
+            stream_dealer
+                .absorb_proof_share(j, proof_shares[j].clone())
+                .unwrap();
+        }
+
+        let batch_proof = batch_dealer.receive_shares_with_rng(&proof_shares, &mut rng).unwrap();
+        let stream_proof = stream_dealer.finish().unwrap();
+        assert_eq!(batch_proof.to_bytes(), stream_proof.to_bytes());
+
+        let mut transcript = Transcript::new(b"StreamingDealerTest");
+        assert!(
+            stream_proof
+                .verify_multiple_with_rng(
+                    &bp_gens,
+                    &pc_gens,
+                    &mut transcript,
+                    &value_commitments,
+                    n,
+                    &mut rng
+                ).is_ok()
+        );
+    }
+
+    #[test]
+    fn proof_bytes_length_and_roundtrip() {
+        let mut rng = util::deterministic_rng([32u8; 32]);
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 2);
+
+        for &n in [8, 16, 32, 64].iter() {
+            for &m in [1, 2].iter() {
+                let values = vec![7u128; m];
+                let blindings = vec![Scalar::from(11u64); m];
+
+                let mut transcript = Transcript::new(b"ProofBytesTest");
+                let (proof, value_commitments) = RangeProof::prove_multiple_with_rng(
+                    &bp_gens,
+                    &pc_gens,
+                    &mut transcript,
+                    &values,
+                    &blindings,
+                    n,
+                    &mut rng,
+                ).unwrap();
+
+                // 4 points, 3 scalars, lg(nm) pairs of points and 2 scalars
+                let lg_nm = (n * m).trailing_zeros() as usize;
+                let bytes = proof.to_bytes();
+                assert_eq!(bytes.len(), (2 * lg_nm + 9) * 32);
+
+                let parsed = RangeProof::from_bytes(&bytes).unwrap();
+                assert_eq!(parsed.to_bytes(), bytes);
+
+                let mut transcript = Transcript::new(b"ProofBytesTest");
+                assert!(
+                    parsed
+                        .verify_multiple_with_rng(
+                            &bp_gens,
+                            &pc_gens,
+                            &mut transcript,
+                            &value_commitments,
+                            n,
+                            &mut rng
+                        ).is_ok()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn proof_hex_roundtrip() {
+        let mut rng = util::deterministic_rng([33u8; 32]);
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 1);
+        let blinding = Scalar::from(11u64);
+
+        let (proof, V) = RangeProof::prove_single_with_rng(
+            &bp_gens,
+            &pc_gens,
+            &mut Transcript::new(b"ProofHexTest"),
+            7,
+            &blinding,
+            32,
+            &mut rng,
+        ).unwrap();
+
+        let hex = proof.to_hex();
+        assert_eq!(hex.len(), 2 * proof.to_bytes().len());
+        let parsed = RangeProof::from_hex(&hex).unwrap();
+        assert_eq!(parsed.to_bytes(), proof.to_bytes());
+        let mut transcript = Transcript::new(b"ProofHexTest");
+        assert!(
+            parsed
+                .verify_single_with_rng(&bp_gens, &pc_gens, &mut transcript, &V, 32, &mut rng)
+                .is_ok()
+        );
+
+        // Invalid hex, odd length, and a truncated proof
+        let bad = format!("z{}", &hex[1..]);
+        assert_eq!(RangeProof::from_hex(&bad).err(), Some(ProofError::FormatError));
+        assert_eq!(RangeProof::from_hex(&hex[1..]).err(), Some(ProofError::FormatError));
+        assert_eq!(
+            RangeProof::from_hex(&hex[..hex.len() - 64]).err(),
+            Some(ProofError::WrongProofLength {
+                expected: 19 * 32,
+                actual: 18 * 32
+            })
+        );
+    }
+
+    #[test]
+    fn rewind_recovers_value_and_blinding() {
+        let mut rng = util::deterministic_rng([34u8; 32]);
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 1);
+        let transcript = || Transcript::new(b"RewindTest");
+
+        let v = 1037578891u128;
+        let blinding = Scalar::from(11u64);
+        let nonce = Scalar::from(42u64);
+
+        let (proof, V) = RangeProof::prove_single_rewindable(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript(),
+            v,
+            &blinding,
+            64,
+            &nonce,
+        ).unwrap();
+
+        // A rewindable proof has the same encoding, and verifies, like
+        // any other single rangeproof.
+        let (plain_proof, plain_V) = RangeProof::prove_single_with_rng(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript(),
+            v,
+            &blinding,
+            64,
+            &mut rng,
+        ).unwrap();
+        assert_eq!(V, plain_V);
+        assert_eq!(proof.to_bytes().len(), plain_proof.to_bytes().len());
+        let proof = RangeProof::from_bytes(&proof.to_bytes()).unwrap();
+        assert!(
+            proof
+                .verify_single_with_rng(&bp_gens, &pc_gens, &mut transcript(), &V, 64, &mut rng)
+                .is_ok()
+        );
+
+        // Rewinding leaves the transcript usable for verification
+        let mut verifier_transcript = transcript();
+        assert_eq!(
+            proof.rewind(&pc_gens, &verifier_transcript, &V, 64, &nonce),
+            Some((v, blinding))
+        );
+        assert!(
+            proof
+                .verify_single_with_rng(
+                    &bp_gens,
+                    &pc_gens,
+                    &mut verifier_transcript,
+                    &V,
+                    64,
+                    &mut rng
+                ).is_ok()
+        );
+
+        // Rewinding with the wrong nonce, transcript or bitsize fails
+        let wrong_nonce = Scalar::from(43u64);
+        assert_eq!(proof.rewind(&pc_gens, &transcript(), &V, 64, &wrong_nonce), None);
+        let wrong_transcript = Transcript::new(b"OtherRewindTest");
+        assert_eq!(proof.rewind(&pc_gens, &wrong_transcript, &V, 64, &nonce), None);
+        assert_eq!(proof.rewind(&pc_gens, &transcript(), &V, 32, &nonce), None);
+        assert_eq!(proof.rewind(&pc_gens, &transcript(), &V, 7, &nonce), None);
+
+        // A proof which wasn't created rewindably can't be rewound
+        assert_eq!(plain_proof.rewind(&pc_gens, &transcript(), &V, 64, &nonce), None);
+    }
+
+    #[test]
+    fn rewind_recovers_128_bit_value() {
+        let mut rng = util::deterministic_rng([35u8; 32]);
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(128, 1);
+        let transcript = || Transcript::new(b"RewindTest");
+
+        let v = u128::max_value() - 5;
+        let blinding = Scalar::from(11u64);
+        let nonce = Scalar::from(42u64);
+
+        let (proof, V) = RangeProof::prove_single_rewindable(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript(),
+            v,
+            &blinding,
+            128,
+            &nonce,
+        ).unwrap();
+        assert!(
+            proof
+                .verify_single_with_rng(&bp_gens, &pc_gens, &mut transcript(), &V, 128, &mut rng)
+                .is_ok()
+        );
+        assert_eq!(
+            proof.rewind(&pc_gens, &transcript(), &V, 128, &nonce),
+            Some((v, blinding))
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn errors_convert_into_boxed_std_errors() {
+        use std::error::Error;
+
+        fn prove_with_bad_bitsize() -> Result<(), Box<Error>> {
+            let pc_gens = PedersenGens::default();
+            let bp_gens = BulletproofGens::new(64, 1);
+            let mut transcript = Transcript::new(b"ErrorTest");
+            RangeProof::prove_single(&bp_gens, &pc_gens, &mut transcript, 7, &Scalar::one(), 7)?;
+            Ok(())
+        }
+
+        let e = prove_with_bad_bitsize().unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "Invalid bitsize 7, must be one of [8, 16, 32, 64, 128]."
+        );
+
+        let e = ProofError::from(MPCError::MaliciousDealer);
+        assert_eq!(
+            e.to_string(),
+            "Internal error during proof creation: Dealer gave a malicious challenge value."
+        );
+        assert_eq!(e.cause().unwrap().to_string(), MPCError::MaliciousDealer.to_string());
+    }
+
+    #[test]
+    fn prove_multiple_with_bitsizes() {
+        let mut rng = util::deterministic_rng([37u8; 32]);
+        let pc_gens = PedersenGens::default();
+        let bitsizes = [8, 16, 64];
+        let padding = padding_bitsizes(&bitsizes);
+        let bp_gens = BulletproofGens::new(64, bitsizes.len() + padding.len());
+        let blindings = [Scalar::from(3u64), Scalar::from(4u64), Scalar::from(5u64)];
+        let transcript = || Transcript::new(b"ProveMixedBitsizesTest");
+
+        let values = [255u128, 65535u128, u64::max_value() as u128];
+        let (proof, value_commitments) = RangeProof::prove_multiple_with_bitsizes_and_rng(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript(),
+            &values,
+            &blindings,
+            &bitsizes,
+            &mut rng,
+        ).unwrap();
+        assert!(
+            proof
+                .verify_multiple_with_bitsizes_and_rng(
+                    &bp_gens,
+                    &pc_gens,
+                    &mut transcript(),
+                    &value_commitments,
+                    &bitsizes,
+                    &mut rng
+                ).is_ok()
+        );
+
+        // The second value doesn't fit in 16 bits
+        let values = [255u128, 65536u128, 0];
+        let (proof, value_commitments) = RangeProof::prove_multiple_with_bitsizes_and_rng(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript(),
+            &values,
+            &blindings,
+            &bitsizes,
+            &mut rng,
+        ).unwrap();
+        assert!(
+            proof
+                .verify_multiple_with_bitsizes_and_rng(
+                    &bp_gens,
+                    &pc_gens,
+                    &mut transcript(),
+                    &value_commitments,
+                    &bitsizes,
+                    &mut rng
+                ).is_err()
+        );
+
+        assert_eq!(
+            RangeProof::prove_multiple_with_bitsizes_and_rng(
+                &bp_gens,
+                &pc_gens,
+                &mut transcript(),
+                &values,
+                &blindings,
+                &bitsizes[..2],
+                &mut rng,
+            ).err(),
+            Some(ProofError::WrongNumBitsizes {
+                expected: 3,
+                actual: 2
+            })
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "mac-messages")]
+    fn authenticated_messages() {
+        use self::messages::*;
+
+        let mut rng = util::deterministic_rng([38u8; 32]);
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(16, 2);
+        let n = 16;
+        let keys = [[1u8; 32], [2u8; 32]];
+        let values = [0u128, 100];
+        let blindings = [Scalar::from(3u64), Scalar::from(4u64)];
+
+        let mut transcript = Transcript::new(b"MacTest");
+        let (dealer, parties, bit_commitments) =
+            start_parties(&bp_gens, &pc_gens, &mut transcript, &values, &blindings);
+        let macs: Vec<_> = bit_commitments
+            .iter()
+            .zip(keys.iter())
+            .map(|(c, k)| authenticate_message(c, k))
+            .collect();
+        let value_commitments: Vec<_> = bit_commitments.iter().map(|c| c.V_j).collect();
+
+        let (dealer, bit_challenge) = dealer
+            .receive_authenticated_bit_commitments(bit_commitments, &macs, &keys)
+            .unwrap();
+
+        let (parties, poly_commitments): (Vec<_>, Vec<_>) = parties
+            .into_iter()
+            .map(|p| p.apply_challenge_with_rng(&bit_challenge, &mut rng))
+            .unzip();
+        let macs: Vec<_> = poly_commitments
+            .iter()
+            .zip(keys.iter())
+            .map(|(c, k)| authenticate_message(c, k))
+            .collect();
+        let (dealer, poly_challenge) = dealer
+            .receive_authenticated_poly_commitments(poly_commitments, &macs, &keys)
+            .unwrap();
+
+        let proof_shares: Vec<_> = parties
+            .into_iter()
+            .map(|p| p.apply_challenge(&poly_challenge).unwrap())
+            .collect();
+        let macs: Vec<_> = proof_shares
+            .iter()
+            .zip(keys.iter())
+            .map(|(s, k)| authenticate_message(s, k))
+            .collect();
+        let proof = dealer
+            .receive_authenticated_shares(&proof_shares, &macs, &keys)
+            .unwrap();
+
+        let mut transcript = Transcript::new(b"MacTest");
+        assert!(
+            proof
+                .verify_multiple_with_rng(
+                    &bp_gens,
+                    &pc_gens,
+                    &mut transcript,
+                    &value_commitments,
+                    n,
+                    &mut rng
+                ).is_ok()
+        );
+
+        // A commitment tampered with in transit, and a MAC under the
+        // wrong key, are both rejected.
+        let mut transcript = Transcript::new(b"MacTest");
+        let (dealer, _, mut bit_commitments) =
+            start_parties(&bp_gens, &pc_gens, &mut transcript, &values, &blindings);
+        let macs: Vec<_> = bit_commitments
+            .iter()
+            .map(|c| authenticate_message(c, &keys[0]))
+            .collect();
+        bit_commitments[0].A_j = bit_commitments[1].A_j;
+        assert_eq!(
+            dealer
+                .receive_authenticated_bit_commitments(bit_commitments, &macs, &keys)
+                .err(),
+            Some(MPCError::InvalidMessageMac {
+                bad_parties: vec![0, 1]
+            })
+        );
+    }
+
+    #[test]
+    fn message_commitments_are_exposed() {
+        use self::party::*;
+
+        let mut rng = util::deterministic_rng([40u8; 32]);
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(8, 1);
+        let blinding = Scalar::from(9u64);
+
+        let party = Party::new(&bp_gens, &pc_gens, 200, blinding, 8).unwrap();
+        let (_, bit_commitment) = party.assign_position_with_rng(0, &mut rng).unwrap();
+
+        assert_eq!(
+            bit_commitment.V_j(),
+            pc_gens.commit(Scalar::from(200u64), blinding).compress()
+        );
+        assert_eq!(bit_commitment.A_j(), bit_commitment.A_j);
+        assert_eq!(bit_commitment.S_j(), bit_commitment.S_j);
+    }
+
+    #[test]
+    fn mpc_is_reproducible_with_seeded_rngs() {
+        use self::dealer::*;
+        use self::party::*;
+
+        let mut rng = util::deterministic_rng([41u8; 32]);
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(8, 2);
+
+        let run = || {
+            let mut party_rngs = [
+                util::deterministic_rng([1u8; 32]),
+                util::deterministic_rng([2u8; 32]),
+            ];
+            let mut transcript = Transcript::new(b"SeededMPCTest");
+            let dealer = Dealer::new(&bp_gens, &pc_gens, &mut transcript, 8, 2).unwrap();
+
+            let (parties, bit_commitments): (Vec<_>, Vec<_>) = party_rngs
+                .iter_mut()
+                .enumerate()
+                .map(|(j, rng)| {
+                    let v = 10 * j as u128 + 7;
+                    Party::new(&bp_gens, &pc_gens, v, Scalar::from(v as u64 + 1), 8)
+                        .unwrap()
+                        .assign_position_with_rng(j, rng)
+                        .unwrap()
+                }).unzip();
+            let (dealer, bit_challenge) = dealer.receive_bit_commitments(bit_commitments).unwrap();
+
+            let (parties, poly_commitments): (Vec<_>, Vec<_>) = parties
+                .into_iter()
+                .zip(party_rngs.iter_mut())
+                .map(|(p, rng)| p.apply_challenge_with_rng(&bit_challenge, rng))
+                .unzip();
+            let (dealer, poly_challenge) =
+                dealer.receive_poly_commitments(poly_commitments).unwrap();
+
+            let proof_shares: Vec<_> = parties
+                .into_iter()
+                .map(|p| p.apply_challenge(&poly_challenge).unwrap())
+                .collect();
+            let proof = dealer
+                .receive_shares_with_rng(&proof_shares, &mut util::deterministic_rng([3u8; 32]))
+                .unwrap();
+            proof.to_bytes()
+        };
+
+        let proof = run();
+        assert_eq!(proof, run());
+
+        // The proof is pinned so that changes to the protocol, or to
+        // how randomness is drawn, are noticed: one line per element.
+        let expected = util::from_hex(concat!(
+            "3689c5908b1814693db4115ef975d43e8cc61ec62cbba1e4639a63b388c2e165",
+            "6a45a130f9b2b3e9b79492179b9158b66fc06fb9ba433e19b9b0496c1126c731",
+            "726a10c9dd5c29576e512cf5e8f69c9527631e3032ab1622a4feb87d15e7b520",
+            "a460405416c1ce39b6c28fae19d8c266a0cf450a8f5e0dab6712ebf288501605",
+            "eb18e024fa121874ca878d71ec6c9bdfcd7eaedb64456dc3326e694ca9db5509",
+            "faa33b8e501af76ab6246979cf577db159f8b46a12088b25c7b8aef66cce830f",
+            "fe48f94cc58e0e72f8a4af1a377a3c1faab4b5d347e625bf5f78c8543b50c40f",
+            "e2ac314333aea25806f31c17bc117af300697ca1f542c01ae18de84875610103",
+            "5232ea4113407724306a2d39ea0d8960d5488cf20b0359ac17ab704ef25b2117",
+            "303023531870505b0cd8c5f370e0702fd0ca5f546ad4b9b89aeebe2155f75459",
+            "4a772d405aa1d6f0359c964ee5740b4a256e48a3b8716ec01f306163e626894c",
+            "c49640381086addb85c6c9e1b9f4d8e8cb84ff1e37b25387c1a841aca31ad11f",
+            "0c7b0affce7cbe555458c915cddb5c6cfb607fb7e0e8d1e544bba0fd205c823c",
+            "aa57203a59da76953d68ebf95854a6f2599ae489aa559f9ec87ba9bce760ed46",
+            "f887aefcddd69357a8519914ff7b4748a208fa103cfb08155a97b9ca0b56783b",
+            "4c83212d3257dc498f92e47c1c33ae0f2fa06619c4db9edb9521c0a167154b06",
+            "e549a3b49c18081ecb279a182269375cd7b2601d0c0b5fa2bcfe9ae7e5f2be07",
+        )).unwrap();
+        assert_eq!(proof, expected);
+    }
+
+    #[test]
+    fn dealer_rejects_invalid_points() {
+        use self::messages::*;
+
+        let mut rng = util::deterministic_rng([42u8; 32]);
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(16, 2);
+
+        let values = [0u128, 1];
+        let blindings = [Scalar::from(1u64), Scalar::from(2u64)];
+
+        // Party 1 sends a value commitment which doesn't decompress
+        let mut transcript = Transcript::new(b"InvalidPointTest");
+        let (dealer, _, mut bit_commitments) =
+            start_parties(&bp_gens, &pc_gens, &mut transcript, &values, &blindings);
+        bit_commitments[1].V_j = CompressedRistretto([0xff; 32]);
+        assert_eq!(
+            dealer.receive_bit_commitments(bit_commitments).err(),
+            Some(MPCError::InvalidPoint {
+                party: 1,
+                field: "V"
+            })
+        );
+
+        // Party 0 sends an encoding of its value commitment with the
+        // unused high bit set, which decodes to the same field element
+        // but isn't canonical
+        let mut transcript = Transcript::new(b"InvalidPointTest");
+        let (dealer, _, mut bit_commitments) =
+            start_parties(&bp_gens, &pc_gens, &mut transcript, &values, &blindings);
+        let mut bytes = bit_commitments[0].V_j.to_bytes();
+        bytes[31] |= 0x80;
+        bit_commitments[0].V_j = CompressedRistretto(bytes);
+        assert_eq!(
+            dealer.verify_bit_commitment(0, &bit_commitments[0]),
+            Err(MPCError::InvalidPoint {
+                party: 0,
+                field: "V"
+            })
+        );
+
+        // Party 0 sends the identity for A, to the streaming dealer
+        let mut transcript = Transcript::new(b"InvalidPointTest");
+        let (dealer, _, mut bit_commitments) =
+            start_parties(&bp_gens, &pc_gens, &mut transcript, &values, &blindings);
+        bit_commitments[0].A_j = RistrettoPoint::identity();
+        assert_eq!(
+            dealer.streaming().absorb_bit_commitment(0, bit_commitments[0]),
+            Err(MPCError::InvalidPoint {
+                party: 0,
+                field: "A"
+            })
+        );
+
+        // Party 1 sends the identity for T_2
+        let mut transcript = Transcript::new(b"InvalidPointTest");
+        let (dealer, parties, bit_commitments) =
+            start_parties(&bp_gens, &pc_gens, &mut transcript, &values, &blindings);
+        let (dealer, bit_challenge) = dealer.receive_bit_commitments(bit_commitments).unwrap();
+
+        // The parties can check the bit challenge, and refuse a zero y
+        // or z from a malicious dealer
+        for &(y, z) in &[
+            (Scalar::zero(), bit_challenge.z),
+            (bit_challenge.y, Scalar::zero()),
+        ] {
+            let mut transcript = Transcript::new(b"InvalidPointTest");
+            let (_, parties, _) =
+                start_parties(&bp_gens, &pc_gens, &mut transcript, &values, &blindings);
+            let party = parties.into_iter().next().unwrap();
+            assert_eq!(
+                party
+                    .try_apply_challenge_with_rng(&BitChallenge { y, z }, &mut rng)
+                    .err(),
+                Some(MPCError::MaliciousDealer)
+            );
+        }
+
+        let (_, mut poly_commitments): (Vec<_>, Vec<_>) = parties
+            .into_iter()
+            .map(|p| {
+                p.try_apply_challenge_with_rng(&bit_challenge, &mut rng)
+                    .unwrap()
+            }).unzip();
+        poly_commitments[1].T_2_j = RistrettoPoint::identity();
+        assert_eq!(
+            dealer.receive_poly_commitments(poly_commitments).err(),
+            Some(MPCError::InvalidPoint {
+                party: 1,
+                field: "T_2"
+            })
+        );
+    }
+
+    #[test]
+    fn accessors_match_byte_layout() {
+        let mut rng = util::deterministic_rng([44u8; 32]);
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(16, 2);
+        let mut transcript = Transcript::new(b"AccessorTest");
+        let (proof, _) = RangeProof::prove_multiple_with_rng(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &[3, 4],
+            &[Scalar::from(5u64), Scalar::from(6u64)],
+            16,
+            &mut rng,
+        ).unwrap();
+
+        let bytes = proof.to_bytes();
+        let chunk = |i: usize| &bytes[i * 32..(i + 1) * 32];
+
+        assert_eq!(proof.A().as_bytes(), chunk(0));
+        assert_eq!(proof.S().as_bytes(), chunk(1));
+        assert_eq!(proof.T_1().as_bytes(), chunk(2));
+        assert_eq!(proof.T_2().as_bytes(), chunk(3));
+        assert_eq!(proof.t_x().as_bytes(), chunk(4));
+        assert_eq!(proof.t_x_blinding().as_bytes(), chunk(5));
+        assert_eq!(proof.e_blinding().as_bytes(), chunk(6));
+
+        // lg(16 * 2) = 5 rounds of (L, R), then a and b
+        let ipp = proof.ipp_proof();
+        assert_eq!(ipp.L_vec().len(), 5);
+        assert_eq!(ipp.R_vec().len(), 5);
+        for i in 0..5 {
+            assert_eq!(ipp.L_vec()[i].as_bytes(), chunk(7 + 2 * i));
+            assert_eq!(ipp.R_vec()[i].as_bytes(), chunk(8 + 2 * i));
+        }
+        assert_eq!(ipp.a().as_bytes(), chunk(17));
+        assert_eq!(ipp.b().as_bytes(), chunk(18));
+        assert_eq!(bytes.len(), 19 * 32);
+    }
+
+    #[test]
+    fn verify_with_deserialized_gens() {
+        use bincode;
+
+        let mut rng = util::deterministic_rng([45u8; 32]);
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 1);
+
+        let mut transcript = Transcript::new(b"CachedGensTest");
+        let (proof, V) = RangeProof::prove_single_with_rng(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            7,
+            &Scalar::from(9u64),
+            64,
+            &mut rng,
+        ).unwrap();
+
+        let cached_pc_gens: PedersenGens =
+            bincode::deserialize(&bincode::serialize(&pc_gens).unwrap()).unwrap();
+        let cached_bp_gens: BulletproofGens =
+            bincode::deserialize(&bincode::serialize(&bp_gens).unwrap()).unwrap();
+
+        let mut transcript = Transcript::new(b"CachedGensTest");
+        assert!(
+            proof
+                .verify_single_with_rng(
+                    &cached_bp_gens,
+                    &cached_pc_gens,
+                    &mut transcript,
+                    &V,
+                    64,
+                    &mut rng
+                ).is_ok()
+        );
+
+        // Swapping in a different valid point anywhere in the table
+        // is caught when parsing: here a G and an H generator from the
+        // middle of the table, and the last H generator.
+        let bytes = bp_gens.to_bytes();
+        let other = bp_gens.share(0).G(1).next().unwrap().compress();
+        for &pos in &[17 + 32 * 31, 17 + 32 * (64 + 17), bytes.len() - 32] {
+            let mut bad_bytes = bytes.clone();
+            bad_bytes[pos..pos + 32].copy_from_slice(other.as_bytes());
+            assert!(BulletproofGens::from_bytes(&bad_bytes).is_err());
+            let encoded = bincode::serialize(&bad_bytes[..]).unwrap();
+            assert!(bincode::deserialize::<BulletproofGens>(&encoded).is_err());
+        }
+    }
+
+    #[test]
+    fn large_aggregated_proof_verification() {
+        // With the `parallel` feature, the verification check of this
+        // proof is split across threads.
+        let (n, m) = (64, 16);
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(n, m);
+        let mut rng = util::deterministic_rng([3u8; 32]);
+
+        let values: Vec<u128> = (0..m as u128).map(|i| 1000 * i).collect();
+        let blindings: Vec<Scalar> = (0..m).map(|_| Scalar::random(&mut rng)).collect();
+
+        let mut transcript = Transcript::new(b"LargeAggregationTest");
+        let (proof, value_commitments) = RangeProof::prove_multiple_with_rng(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &values,
+            &blindings,
+            n,
+            &mut rng,
+        ).unwrap();
+
+        let mut transcript = Transcript::new(b"LargeAggregationTest");
+        assert!(
+            proof
+                .verify_multiple_with_rng(
+                    &bp_gens,
+                    &pc_gens,
+                    &mut transcript,
+                    &value_commitments,
+                    n,
+                    &mut rng
+                ).is_ok()
+        );
+
+        // Corrupt the final inner-product scalar `b`
+        let mut bytes = proof.to_bytes();
+        let pos = bytes.len() - 32;
+        bytes[pos] ^= 1;
+        let bad_proof = RangeProof::from_bytes(&bytes).unwrap();
+        let mut transcript = Transcript::new(b"LargeAggregationTest");
+        assert_eq!(
+            bad_proof.verify_multiple_with_rng(
+                &bp_gens,
+                &pc_gens,
+                &mut transcript,
+                &value_commitments,
+                n,
+                &mut rng
+            ),
+            Err(ProofError::VerificationError)
+        );
+
+        // Swap two value commitments
+        let mut swapped = value_commitments.clone();
+        swapped.swap(0, 1);
+        let mut transcript = Transcript::new(b"LargeAggregationTest");
+        assert_eq!(
+            proof.verify_multiple_with_rng(
+                &bp_gens,
+                &pc_gens,
+                &mut transcript,
+                &swapped,
+                n,
+                &mut rng
+            ),
+            Err(ProofError::VerificationError)
+        );
+    }
+
+    #[test]
+    fn proofs_and_messages_compare_by_encoding() {
+        use self::party::*;
+
+        let mut rng = util::deterministic_rng([46u8; 32]);
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 1);
+        let blinding = Scalar::from(11u64);
+
+        let prove = |seed: u8| {
+            let mut transcript = Transcript::new(b"EqualityTest");
+            RangeProof::prove_single_with_rng(
+                &bp_gens,
+                &pc_gens,
+                &mut transcript,
+                99,
+                &blinding,
+                32,
+                &mut util::deterministic_rng([seed; 32]),
+            ).unwrap()
+        };
+        let (proof, V) = prove(1);
+        let (same_proof, _) = prove(1);
+        let (other_proof, other_V) = prove(2);
+
+        // Both proofs verify for the same commitment, but differ in bytes
+        assert_eq!(V, other_V);
+        for p in [&proof, &other_proof].iter() {
+            let mut transcript = Transcript::new(b"EqualityTest");
+            assert!(
+                p.verify_single_with_rng(&bp_gens, &pc_gens, &mut transcript, &V, 32, &mut rng)
+                    .is_ok()
+            );
+        }
+        assert!(proof == same_proof);
+        assert!(proof != other_proof);
+        let parsed = RangeProof::from_bytes(&proof.to_bytes()).unwrap();
+        assert!(bool::from(proof.ct_eq(&parsed)));
+
+        let bit_commitment = |seed: u8| {
+            let party = Party::new(&bp_gens, &pc_gens, 99, blinding, 32).unwrap();
+            let (_, bit_commitment) = party
+                .assign_position_with_rng(0, &mut util::deterministic_rng([seed; 32]))
+                .unwrap();
+            bit_commitment
+        };
+        assert!(bit_commitment(1) == bit_commitment(1));
+        assert!(bit_commitment(1) != bit_commitment(2));
+    }
+
+    #[test]
+    fn size_errors_report_lengths() {
+        use self::dealer::*;
+        use self::party::*;
+
+        let mut rng = util::deterministic_rng([47u8; 32]);
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(16, 2);
+        let blinding = Scalar::from(5u64);
+
+        // Three commitments can't be an aggregated proof
+        let (proof, Vs) = RangeProof::prove_multiple_with_rng(
+            &bp_gens,
+            &pc_gens,
+            &mut Transcript::new(b"SizeErrorTest"),
+            &[1, 2],
+            &[blinding; 2],
+            16,
+            &mut rng,
+        ).unwrap();
+        let mut three_Vs = Vs.clone();
+        three_Vs.push(Vs[0]);
+        assert_eq!(
+            proof.verify_multiple_with_rng(
+                &bp_gens,
+                &pc_gens,
+                &mut Transcript::new(b"SizeErrorTest"),
+                &three_Vs,
+                16,
+                &mut rng
+            ),
+            Err(ProofError::InvalidAggregation {
+                got: 3,
+                reason: "the number of commitments must be a power of two"
+            })
+        );
+
+        assert_eq!(
+            Dealer::new(
+                &bp_gens,
+                &pc_gens,
+                &mut Transcript::new(b"SizeErrorTest"),
+                16,
+                4
+            ).err(),
+            Some(MPCError::InsufficientPartyCapacity {
+                needed: 4,
+                available: 2
+            })
+        );
+        assert_eq!(
+            Party::new(&bp_gens, &pc_gens, 1, blinding, 32).err(),
+            Some(MPCError::InsufficientGeneratorCapacity {
+                needed: 32,
+                available: 16
+            })
+        );
+
+        // The dealer reports how many messages it expected
+        let parties: Vec<_> = (0..2)
+            .map(|j| {
+                Party::new(&bp_gens, &pc_gens, 1, blinding, 16)
+                    .unwrap()
+                    .assign_position_with_rng(j, &mut rng)
+                    .unwrap()
+            }).collect();
+        let (parties, bit_commitments): (Vec<_>, Vec<_>) = parties.into_iter().unzip();
+        let mut transcript = Transcript::new(b"SizeErrorTest");
+        let dealer = Dealer::new(&bp_gens, &pc_gens, &mut transcript, 16, 2).unwrap();
+        assert_eq!(
+            dealer
+                .receive_bit_commitments(bit_commitments[..1].to_vec())
+                .err(),
+            Some(MPCError::WrongNumBitCommitments {
+                expected: 2,
+                actual: 1
+            })
+        );
+        let mut transcript = Transcript::new(b"SizeErrorTest");
+        let dealer = Dealer::new(&bp_gens, &pc_gens, &mut transcript, 16, 2).unwrap();
+        let (dealer, bit_challenge) = dealer.receive_bit_commitments(bit_commitments).unwrap();
+        let (_, poly_commitments): (Vec<_>, Vec<_>) = parties
+            .into_iter()
+            .map(|p| p.apply_challenge_with_rng(&bit_challenge, &mut rng))
+            .unzip();
+        assert_eq!(
+            dealer.receive_poly_commitments(poly_commitments[..1].to_vec()).err(),
+            Some(MPCError::WrongNumPolyCommitments {
+                expected: 2,
+                actual: 1
+            })
+        );
+
+        // Mismatched generator tables
+        let G = vec![vec![pc_gens.B; 4]; 2];
+        let H = vec![vec![pc_gens.B_blinding; 4], vec![pc_gens.B_blinding; 3]];
+        assert_eq!(
+            BulletproofGens::from_generators(G, H).err(),
+            Some(ProofError::InvalidGeneratorsLength {
+                expected: 4,
+                actual: 3
+            })
+        );
+
+        let e = ProofError::InvalidGeneratorsLength {
+            expected: 4,
+            actual: 3,
+        };
+        assert_eq!(e.to_string(), "Invalid generators length: expected 4, got 3.");
+    }
+
+    #[test]
+    fn dealer_rejects_bad_parameters() {
+        use self::dealer::*;
+
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 4);
+        let new_dealer = |n: usize, m: usize| {
+            let mut transcript = Transcript::new(b"DealerParamsTest");
+            let result = Dealer::new(&bp_gens, &pc_gens, &mut transcript, n, m);
+            result.err()
+        };
+
+        assert_eq!(new_dealer(64, 4), None);
+        assert_eq!(
+            new_dealer(48, 4),
+            Some(MPCError::InvalidBitsize {
+                got: 48,
+                allowed: &[8, 16, 32, 64, 128]
+            })
+        );
+        assert_eq!(
+            new_dealer(64, 3),
+            Some(MPCError::InvalidAggregation {
+                got: 3,
+                reason: "the number of parties must be a power of two"
+            })
+        );
+        assert_eq!(
+            new_dealer(64, 0),
+            Some(MPCError::InvalidAggregation {
+                got: 0,
+                reason: "the number of parties must be a power of two"
+            })
+        );
+        assert_eq!(
+            new_dealer(128, 4),
+            Some(MPCError::InsufficientGeneratorCapacity {
+                needed: 128,
+                available: 64
+            })
+        );
+        assert_eq!(
+            new_dealer(64, 8),
+            Some(MPCError::InsufficientPartyCapacity {
+                needed: 8,
+                available: 4
+            })
+        );
+
+        let mut transcript = Transcript::new(b"DealerParamsTest");
+        assert_eq!(
+            Dealer::new_with_bitsizes(&bp_gens, &pc_gens, &mut transcript, &[]).err(),
+            Some(MPCError::InvalidAggregation {
+                got: 0,
+                reason: "there must be at least one party"
+            })
+        );
+
+        assert_eq!(
+            MPCError::InvalidBitsize {
+                got: 48,
+                allowed: ALLOWED_BITSIZES
+            }.to_string(),
+            "Invalid bitsize 48, must be one of [8, 16, 32, 64, 128]."
+        );
+        assert_eq!(
+            new_dealer(64, 3).unwrap().to_string(),
+            "Invalid aggregation size 3: the number of parties must be a power of two."
+        );
     }
 }
@@ -6,18 +6,36 @@
 //! a compile error to perform the steps out of order or to repeat a
 //! step.
 //!
+//! A party which needs to wait for the dealer outside of memory can
+//! `save` its state into a serializable form, and `resume` it later
+//! given the public parameters.  The saved states contain the party's
+//! secrets, so they must be stored encrypted, and a saved state must
+//! be resumed at most once: answering two different challenges from
+//! the same state reveals the party's secret value.
+//!
+//! The party states overwrite their secrets with zeroes when they
+//! are dropped, including when they are consumed by a state
+//! transition.
+//!
 //! For more explanation of how the `dealer`, `party`, and `messages`
 //! modules orchestrate the protocol execution, see the documentation
 //! in the [`aggregation`](::aggregation) module.
 
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use clear_on_drop::clear::Clear;
 use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
 use curve25519_dalek::scalar::Scalar;
 use curve25519_dalek::traits::MultiscalarMul;
 
 use errors::MPCError;
 use generators::{BulletproofGens, PedersenGens};
+#[cfg(feature = "std")]
 use rand;
-use std::iter;
+use rand::{CryptoRng, RngCore};
+use core::iter;
+use range_proof::ALLOWED_BITSIZES;
 use util;
 
 use super::messages::*;
@@ -30,18 +48,24 @@ impl Party {
     pub fn new<'a>(
         bp_gens: &'a BulletproofGens,
         pc_gens: &'a PedersenGens,
-        v: u64,
+        v: u128,
         v_blinding: Scalar,
         n: usize,
     ) -> Result<PartyAwaitingPosition<'a>, MPCError> {
-        if !(n == 8 || n == 16 || n == 32 || n == 64) {
-            return Err(MPCError::InvalidBitsize);
+        if !(n == 8 || n == 16 || n == 32 || n == 64 || n == 128) {
+            return Err(MPCError::InvalidBitsize {
+                got: n,
+                allowed: ALLOWED_BITSIZES,
+            });
         }
         if bp_gens.gens_capacity < n {
-            return Err(MPCError::InvalidGeneratorsLength);
+            return Err(MPCError::InsufficientGeneratorCapacity {
+                needed: n,
+                available: bp_gens.gens_capacity,
+            });
         }
 
-        let V = pc_gens.commit(v.into(), v_blinding).compress();
+        let V = pc_gens.commit(util::scalar_from_u128(v), v_blinding).compress();
 
         Ok(PartyAwaitingPosition {
             bp_gens,
@@ -59,28 +83,95 @@ pub struct PartyAwaitingPosition<'a> {
     bp_gens: &'a BulletproofGens,
     pc_gens: &'a PedersenGens,
     n: usize,
-    v: u64,
+    v: u128,
     v_blinding: Scalar,
     V: CompressedRistretto,
 }
 
 impl<'a> PartyAwaitingPosition<'a> {
+    /// Saves the party's state into a serializable form, which can be
+    /// resumed with [`SavedPartyAwaitingPosition::resume`].
+    pub fn save(self) -> SavedPartyAwaitingPosition {
+        SavedPartyAwaitingPosition {
+            n: self.n,
+            v: self.v,
+            v_blinding: self.v_blinding,
+        }
+    }
+
     /// Assigns a position in the aggregated proof to this party,
     /// allowing the party to commit to the bits of their value.
+    ///
+    /// This assumes that all parties prove ranges of the same
+    /// bitsize.  For aggregations with mixed bitsizes, use
+    /// [`assign_position_with_offset`](PartyAwaitingPosition::assign_position_with_offset).
+    #[cfg(feature = "std")]
     pub fn assign_position(
         self,
         j: usize,
     ) -> Result<(PartyAwaitingBitChallenge<'a>, BitCommitment), MPCError> {
-        // XXX use transcript RNG
-        let mut rng = rand::thread_rng();
+        self.assign_position_with_rng(j, &mut rand::thread_rng())
+    }
+
+    /// Assigns a position in the aggregated proof to this party,
+    /// drawing the party's blinding factors from `rng`.
+    pub fn assign_position_with_rng<T: RngCore + CryptoRng>(
+        self,
+        j: usize,
+        rng: &mut T,
+    ) -> Result<(PartyAwaitingBitChallenge<'a>, BitCommitment), MPCError> {
+        let offset = j * self.n;
+        self.assign_position_with_offset_and_rng(j, offset, rng)
+    }
+
+    /// Assigns a position `j` in an aggregated proof where parties
+    /// may prove ranges of different bitsizes.
+    ///
+    /// The `offset` is the position of this party's first bit in the
+    /// aggregated bit vector, that is, the sum of the bitsizes of
+    /// parties `0..j`.
+    #[cfg(feature = "std")]
+    pub fn assign_position_with_offset(
+        self,
+        j: usize,
+        offset: usize,
+    ) -> Result<(PartyAwaitingBitChallenge<'a>, BitCommitment), MPCError> {
+        self.assign_position_with_offset_and_rng(j, offset, &mut rand::thread_rng())
+    }
+
+    /// Assigns a position `j` and bit `offset` in an aggregated
+    /// proof, drawing the party's blinding factors from `rng`.
+    pub fn assign_position_with_offset_and_rng<T: RngCore + CryptoRng>(
+        self,
+        j: usize,
+        offset: usize,
+        rng: &mut T,
+    ) -> Result<(PartyAwaitingBitChallenge<'a>, BitCommitment), MPCError> {
+        self.assign_position_with_message_and_rng(j, offset, Scalar::zero(), rng)
+    }
 
+    /// Assigns a position like
+    /// [`assign_position_with_offset_and_rng`](PartyAwaitingPosition::assign_position_with_offset_and_rng),
+    /// but adds `message` to the blinding factor of \\(A\\), so that
+    /// it can be recovered from the proof by anyone who can reproduce
+    /// the randomness drawn from `rng`.
+    pub(crate) fn assign_position_with_message_and_rng<T: RngCore + CryptoRng>(
+        self,
+        j: usize,
+        offset: usize,
+        message: Scalar,
+        rng: &mut T,
+    ) -> Result<(PartyAwaitingBitChallenge<'a>, BitCommitment), MPCError> {
         if self.bp_gens.party_capacity <= j {
-            return Err(MPCError::InvalidGeneratorsLength);
+            return Err(MPCError::InsufficientPartyCapacity {
+                needed: j + 1,
+                available: self.bp_gens.party_capacity,
+            });
         }
 
         let bp_share = self.bp_gens.share(j);
 
-        let a_blinding = Scalar::random(&mut rng);
+        let a_blinding = Scalar::random(rng) + message;
         // Compute A = <a_L, G> + <a_R, H> + a_blinding * B_blinding
         let mut A = self.pc_gens.B_blinding * a_blinding;
 
@@ -96,9 +187,9 @@ impl<'a> PartyAwaitingPosition<'a> {
             i += 1;
         }
 
-        let s_blinding = Scalar::random(&mut rng);
-        let s_L: Vec<Scalar> = (0..self.n).map(|_| Scalar::random(&mut rng)).collect();
-        let s_R: Vec<Scalar> = (0..self.n).map(|_| Scalar::random(&mut rng)).collect();
+        let s_blinding = Scalar::random(rng);
+        let s_L: Vec<Scalar> = (0..self.n).map(|_| Scalar::random(rng)).collect();
+        let s_R: Vec<Scalar> = (0..self.n).map(|_| Scalar::random(rng)).collect();
 
         // Compute S = <s_L, G> + <s_R, H> + s_blinding * B_blinding
         let S = RistrettoPoint::multiscalar_mul(
@@ -120,6 +211,7 @@ impl<'a> PartyAwaitingPosition<'a> {
             v_blinding: self.v_blinding,
             pc_gens: self.pc_gens,
             j,
+            offset,
             a_blinding,
             s_blinding,
             s_L,
@@ -129,13 +221,37 @@ impl<'a> PartyAwaitingPosition<'a> {
     }
 }
 
+/// The saved state of a [`PartyAwaitingPosition`].
+///
+/// This contains the party's secret value, so it must be stored
+/// encrypted.
+#[derive(Serialize, Deserialize)]
+pub struct SavedPartyAwaitingPosition {
+    n: usize,
+    v: u128,
+    v_blinding: Scalar,
+}
+
+impl SavedPartyAwaitingPosition {
+    /// Resumes the saved state, using the same generators as the
+    /// party was constructed with.
+    pub fn resume<'a>(
+        self,
+        bp_gens: &'a BulletproofGens,
+        pc_gens: &'a PedersenGens,
+    ) -> Result<PartyAwaitingPosition<'a>, MPCError> {
+        Party::new(bp_gens, pc_gens, self.v, self.v_blinding, self.n)
+    }
+}
+
 /// A party which has committed to the bits of its value
 /// and is waiting for the aggregated value challenge from the dealer.
 pub struct PartyAwaitingBitChallenge<'a> {
     n: usize, // bitsize of the range
-    v: u64,
+    v: u128,
     v_blinding: Scalar,
     j: usize,
+    offset: usize, // position of the first bit in the aggregation
     pc_gens: &'a PedersenGens,
     a_blinding: Scalar,
     s_blinding: Scalar,
@@ -144,16 +260,41 @@ pub struct PartyAwaitingBitChallenge<'a> {
 }
 
 impl<'a> PartyAwaitingBitChallenge<'a> {
+    /// Saves the party's state into a serializable form, which can be
+    /// resumed with [`SavedPartyAwaitingBitChallenge::resume`].
+    pub fn save(self) -> SavedPartyAwaitingBitChallenge {
+        SavedPartyAwaitingBitChallenge {
+            n: self.n,
+            v: self.v,
+            v_blinding: self.v_blinding,
+            j: self.j,
+            offset: self.offset,
+            a_blinding: self.a_blinding,
+            s_blinding: self.s_blinding,
+            s_L: self.s_L.clone(),
+            s_R: self.s_R.clone(),
+        }
+    }
+
     /// Receive a [`BitChallenge`] from the dealer and use it to
     /// compute commitments to the party's polynomial coefficients.
+    #[cfg(feature = "std")]
     pub fn apply_challenge(
         self,
         vc: &BitChallenge,
     ) -> (PartyAwaitingPolyChallenge, PolyCommitment) {
-        let mut rng = rand::thread_rng();
+        self.apply_challenge_with_rng(vc, &mut rand::thread_rng())
+    }
 
+    /// Receive a [`BitChallenge`] from the dealer, drawing the
+    /// blinding factors for the polynomial commitments from `rng`.
+    pub fn apply_challenge_with_rng<T: RngCore + CryptoRng>(
+        self,
+        vc: &BitChallenge,
+        rng: &mut T,
+    ) -> (PartyAwaitingPolyChallenge, PolyCommitment) {
         let n = self.n;
-        let offset_y = util::scalar_exp_vartime(&vc.y, (self.j * n) as u64);
+        let offset_y = util::scalar_exp_vartime(&vc.y, self.offset as u64);
         let offset_z = util::scalar_exp_vartime(&vc.z, self.j as u64);
 
         // Calculate t by calculating vectors l0, l1, r0, r1 and multiplying
@@ -164,7 +305,7 @@ impl<'a> PartyAwaitingBitChallenge<'a> {
         let mut exp_y = offset_y; // start at y^j
         let mut exp_2 = Scalar::one(); // start at 2^0 = 1
         for i in 0..n {
-            let a_L_i = Scalar::from((self.v >> i) & 1);
+            let a_L_i = Scalar::from(((self.v >> i) & 1) as u64);
             let a_R_i = a_L_i - Scalar::one();
 
             l_poly.0[i] = a_L_i - vc.z;
@@ -179,8 +320,8 @@ impl<'a> PartyAwaitingBitChallenge<'a> {
         let t_poly = l_poly.inner_product(&r_poly);
 
         // Generate x by committing to T_1, T_2 (line 49-54)
-        let t_1_blinding = Scalar::random(&mut rng);
-        let t_2_blinding = Scalar::random(&mut rng);
+        let t_1_blinding = Scalar::random(rng);
+        let t_2_blinding = Scalar::random(rng);
         let T_1 = self.pc_gens.commit(t_poly.1, t_1_blinding);
         let T_2 = self.pc_gens.commit(t_poly.2, t_2_blinding);
 
@@ -204,10 +345,138 @@ impl<'a> PartyAwaitingBitChallenge<'a> {
 
         (papc, poly_commitment)
     }
+
+    /// Receive a [`BitChallenge`] from the dealer, checking that it
+    /// is nonzero before computing the polynomial commitments.
+    ///
+    /// Returns [`MPCError::MaliciousDealer`] if either challenge value
+    /// is zero.
+    #[cfg(feature = "std")]
+    pub fn try_apply_challenge(
+        self,
+        vc: &BitChallenge,
+    ) -> Result<(PartyAwaitingPolyChallenge, PolyCommitment), MPCError> {
+        self.try_apply_challenge_with_rng(vc, &mut rand::thread_rng())
+    }
+
+    /// Receive a [`BitChallenge`] from the dealer, checking that it
+    /// is nonzero, and drawing the blinding factors for the polynomial
+    /// commitments from `rng`.
+    ///
+    /// Returns [`MPCError::MaliciousDealer`] if either challenge value
+    /// is zero.
+    pub fn try_apply_challenge_with_rng<T: RngCore + CryptoRng>(
+        self,
+        vc: &BitChallenge,
+        rng: &mut T,
+    ) -> Result<(PartyAwaitingPolyChallenge, PolyCommitment), MPCError> {
+        // A zero y would annihilate the bits after the party's first,
+        // and a zero z would cancel the party's value commitment.
+        if vc.y == Scalar::zero() || vc.z == Scalar::zero() {
+            return Err(MPCError::MaliciousDealer);
+        }
+        Ok(self.apply_challenge_with_rng(vc, rng))
+    }
+}
+
+/// The saved state of a [`PartyAwaitingBitChallenge`].
+///
+/// This contains the party's secret value and blinding factors, so
+/// it must be stored encrypted, and resumed at most once.
+#[derive(Serialize, Deserialize)]
+pub struct SavedPartyAwaitingBitChallenge {
+    n: usize,
+    v: u128,
+    v_blinding: Scalar,
+    j: usize,
+    offset: usize,
+    a_blinding: Scalar,
+    s_blinding: Scalar,
+    s_L: Vec<Scalar>,
+    s_R: Vec<Scalar>,
+}
+
+impl SavedPartyAwaitingBitChallenge {
+    /// Resumes the saved state, using the same Pedersen generators as
+    /// the party was constructed with.
+    pub fn resume<'a>(
+        self,
+        pc_gens: &'a PedersenGens,
+    ) -> Result<PartyAwaitingBitChallenge<'a>, MPCError> {
+        if self.s_L.len() != self.n || self.s_R.len() != self.n {
+            return Err(MPCError::InvalidSavedState);
+        }
+
+        Ok(PartyAwaitingBitChallenge {
+            n: self.n,
+            v: self.v,
+            v_blinding: self.v_blinding,
+            j: self.j,
+            offset: self.offset,
+            pc_gens,
+            a_blinding: self.a_blinding,
+            s_blinding: self.s_blinding,
+            s_L: self.s_L.clone(),
+            s_R: self.s_R.clone(),
+        })
+    }
+}
+
+/// Overwrite secrets with zeroes when they go out of scope.
+impl<'a> Drop for PartyAwaitingPosition<'a> {
+    fn drop(&mut self) {
+        self.v.clear();
+        self.v_blinding.clear();
+    }
+}
+
+/// Overwrite secrets with zeroes when they go out of scope.
+impl Drop for SavedPartyAwaitingPosition {
+    fn drop(&mut self) {
+        self.v.clear();
+        self.v_blinding.clear();
+    }
+}
+
+/// Overwrite secrets with zeroes when they go out of scope.
+impl<'a> Drop for PartyAwaitingBitChallenge<'a> {
+    fn drop(&mut self) {
+        self.v.clear();
+        self.v_blinding.clear();
+        self.a_blinding.clear();
+        self.s_blinding.clear();
+        for e in self.s_L.iter_mut() {
+            e.clear();
+        }
+        for e in self.s_R.iter_mut() {
+            e.clear();
+        }
+    }
+}
+
+/// Overwrite secrets with zeroes when they go out of scope.
+impl Drop for SavedPartyAwaitingBitChallenge {
+    fn drop(&mut self) {
+        self.v.clear();
+        self.v_blinding.clear();
+        self.a_blinding.clear();
+        self.s_blinding.clear();
+        for e in self.s_L.iter_mut() {
+            e.clear();
+        }
+        for e in self.s_R.iter_mut() {
+            e.clear();
+        }
+    }
 }
 
 /// A party which has committed to their polynomial coefficents
 /// and is waiting for the polynomial challenge from the dealer.
+///
+/// This state does not depend on any public parameters, so it is
+/// serializable as is.  It contains the party's secrets, so it must
+/// be stored encrypted, and resumed at most once.
+#[derive(Serialize, Deserialize)]
 pub struct PartyAwaitingPolyChallenge {
     z: Scalar,
     offset_z: Scalar,
@@ -252,3 +521,16 @@ impl PartyAwaitingPolyChallenge {
         })
     }
 }
+
+/// Overwrite secrets with zeroes when they go out of scope.
+///
+/// The polynomials clear themselves when they are dropped.
+impl Drop for PartyAwaitingPolyChallenge {
+    fn drop(&mut self) {
+        self.v_blinding.clear();
+        self.a_blinding.clear();
+        self.s_blinding.clear();
+        self.t_1_blinding.clear();
+        self.t_2_blinding.clear();
+    }
+}
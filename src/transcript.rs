@@ -5,17 +5,86 @@ use curve25519_dalek::ristretto::CompressedRistretto;
 use curve25519_dalek::scalar::Scalar;
 use merlin::Transcript;
 
+/// Extension trait for the [`Transcript`] operations used by the
+/// proofs in this crate.
+///
+/// # Binding application context
+///
+/// A proof is bound to everything committed to the transcript before
+/// it was created, so applications can bind a proof to their own
+/// context (such as a transaction ID) by committing it to the
+/// transcript before proving, and committing the same data before
+/// verifying.  A proof created with one context will not verify with
+/// another.
+///
+/// Merlin frames every message with its label and length, so
+/// application messages can't be confused with the crate's messages.
+/// Nonetheless, applications should keep their messages out of the
+/// labels used by the crate:
+///
+/// * the domain separators use the labels `dom-sep`, `n`, `m`, `min`
+///   and `max`;
+/// * the range proof commits `V`, `A`, `S`, `T_1`, `T_2`, `t_x`,
+///   `t_x_blinding` and `e_blinding`, and draws `x`, `y`, `z` and `w`;
+/// * the inner-product proof commits `L` and `R`, and draws `u`;
+/// * [`fork`](TranscriptProtocol::fork) and
+///   [`merge`](TranscriptProtocol::merge) commit `fork`, `branch` and
+///   `merged`, and draw `merge`.
+///
+/// A good practice is to begin the application's messages with its
+/// own domain separator, and to complete them before passing the
+/// transcript to a prover or verifier.
+///
+/// # Typed messages
+///
+/// Applications and external gadgets can use the `commit_*` and
+/// `challenge_scalar` methods to commit their own messages with the
+/// same encodings as the crate.  These methods panic if the `label` is
+/// empty, since an unlabeled message is almost certainly a mistake.
+///
+/// # Forking
+///
+/// Independent sub-protocols can run on the two branches of a
+/// [`fork`](TranscriptProtocol::fork)ed transcript, in any order or in
+/// parallel, and then rejoin with
+/// [`merge`](TranscriptProtocol::merge).  The verifier must fork and
+/// merge with the same labels.
 pub trait TranscriptProtocol {
     /// Commit a domain separator for an `n`-bit, `m`-party range proof.
     fn rangeproof_domain_sep(&mut self, n: u64, m: u64);
+    /// Commit a domain separator for an aggregated range proof whose
+    /// parties prove ranges of different `bitsizes`.
+    fn mixed_rangeproof_domain_sep(&mut self, bitsizes: &[usize]);
+    /// Commit a domain separator for a proof that a value lies in
+    /// the interval \\([min, max]\\).
+    fn interval_domain_sep(&mut self, min: u128, max: u128);
     /// Commit a domain separator for a length-`n` inner product proof.
     fn innerproduct_domain_sep(&mut self, n: u64);
+    /// Commit a `u64` with the given `label`, in little-endian encoding.
+    fn commit_u64(&mut self, label: &'static [u8], value: u64);
     /// Commit a `scalar` with the given `label`.
     fn commit_scalar(&mut self, label: &'static [u8], scalar: &Scalar);
     /// Commit a `point` with the given `label`.
     fn commit_point(&mut self, label: &'static [u8], point: &CompressedRistretto);
     /// Compute a `label`ed challenge variable.
     fn challenge_scalar(&mut self, label: &'static [u8]) -> Scalar;
+    /// Splits the transcript into two branches, each of which is
+    /// bound to the current transcript state, the `label`, and its
+    /// own position.
+    fn fork(self, label: &'static [u8]) -> (Transcript, Transcript)
+    where
+        Self: Sized;
+    /// Rejoins two branches into a transcript labeled with `label`,
+    /// which is bound to the final states of both branches.
+    ///
+    /// The result doesn't depend on the order of the branches.
+    fn merge(left: Transcript, right: Transcript, label: &'static [u8]) -> Transcript
+    where
+        Self: Sized;
+}
+
+fn check_label(label: &'static [u8]) {
+    assert!(!label.is_empty(), "transcript labels must be non-empty");
 }
 
 fn le_u64(value: u64) -> [u8; 8] {
@@ -24,6 +93,13 @@ fn le_u64(value: u64) -> [u8; 8] {
     value_bytes
 }
 
+fn le_u128(value: u128) -> [u8; 16] {
+    let mut value_bytes = [0u8; 16];
+    LittleEndian::write_u64(&mut value_bytes[..8], value as u64);
+    LittleEndian::write_u64(&mut value_bytes[8..], (value >> 64) as u64);
+    value_bytes
+}
+
 impl TranscriptProtocol for Transcript {
     fn rangeproof_domain_sep(&mut self, n: u64, m: u64) {
         self.commit_bytes(b"dom-sep", b"rangeproof");
@@ -31,23 +107,125 @@ impl TranscriptProtocol for Transcript {
         self.commit_bytes(b"m", &le_u64(m));
     }
 
+    fn mixed_rangeproof_domain_sep(&mut self, bitsizes: &[usize]) {
+        self.commit_bytes(b"dom-sep", b"mixed-rangeproof");
+        self.commit_bytes(b"m", &le_u64(bitsizes.len() as u64));
+        for n in bitsizes.iter() {
+            self.commit_bytes(b"n", &le_u64(*n as u64));
+        }
+    }
+
+    fn interval_domain_sep(&mut self, min: u128, max: u128) {
+        self.commit_bytes(b"dom-sep", b"interval");
+        self.commit_bytes(b"min", &le_u128(min));
+        self.commit_bytes(b"max", &le_u128(max));
+    }
+
     fn innerproduct_domain_sep(&mut self, n: u64) {
         self.commit_bytes(b"dom-sep", b"ipp");
         self.commit_bytes(b"n", &le_u64(n));
     }
 
+    fn commit_u64(&mut self, label: &'static [u8], value: u64) {
+        check_label(label);
+        self.commit_bytes(label, &le_u64(value));
+    }
+
     fn commit_scalar(&mut self, label: &'static [u8], scalar: &Scalar) {
+        check_label(label);
         self.commit_bytes(label, scalar.as_bytes());
     }
 
     fn commit_point(&mut self, label: &'static [u8], point: &CompressedRistretto) {
+        check_label(label);
         self.commit_bytes(label, point.as_bytes());
     }
 
     fn challenge_scalar(&mut self, label: &'static [u8]) -> Scalar {
+        check_label(label);
         let mut buf = [0u8; 64];
         self.challenge_bytes(label, &mut buf);
 
         Scalar::from_bytes_mod_order_wide(&buf)
     }
+
+    fn fork(mut self, label: &'static [u8]) -> (Transcript, Transcript) {
+        check_label(label);
+        self.commit_bytes(b"fork", label);
+
+        let mut left = self.clone();
+        left.commit_bytes(b"branch", b"left");
+        let mut right = self;
+        right.commit_bytes(b"branch", b"right");
+        (left, right)
+    }
+
+    fn merge(mut left: Transcript, mut right: Transcript, label: &'static [u8]) -> Transcript {
+        check_label(label);
+        let mut digests = [[0u8; 32]; 2];
+        left.challenge_bytes(b"merge", &mut digests[0]);
+        right.challenge_bytes(b"merge", &mut digests[1]);
+        // Each branch already commits to its position, so the digests
+        // can be committed in sorted order.
+        digests.sort();
+
+        let mut merged = Transcript::new(label);
+        merged.commit_bytes(b"merged", &digests[0]);
+        merged.commit_bytes(b"merged", &digests[1]);
+        merged
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commit_u64_matches_domain_separators() {
+        let mut t1 = Transcript::new(b"TranscriptTest");
+        t1.innerproduct_domain_sep(64);
+
+        let mut t2 = Transcript::new(b"TranscriptTest");
+        t2.commit_bytes(b"dom-sep", b"ipp");
+        t2.commit_u64(b"n", 64);
+
+        assert_eq!(t1.challenge_scalar(b"c"), t2.challenge_scalar(b"c"));
+    }
+
+    #[test]
+    fn merge_is_order_independent() {
+        let run = |context: &[u8], left_msg: &[u8], right_msg: &[u8], swap: bool| {
+            let mut transcript = Transcript::new(b"TranscriptTest");
+            transcript.commit_bytes(b"context", context);
+            let (mut left, mut right) = transcript.fork(b"subproofs");
+            if swap {
+                right.commit_bytes(b"msg", right_msg);
+                left.commit_bytes(b"msg", left_msg);
+            } else {
+                left.commit_bytes(b"msg", left_msg);
+                right.commit_bytes(b"msg", right_msg);
+            }
+            let mut merged = if swap {
+                Transcript::merge(right, left, b"joined")
+            } else {
+                Transcript::merge(left, right, b"joined")
+            };
+            merged.challenge_scalar(b"c")
+        };
+
+        let c = run(b"ctx", b"a", b"b", false);
+        assert_eq!(c, run(b"ctx", b"a", b"b", true));
+
+        // The merged transcript is bound to the parent state and to
+        // the position of each branch's messages.
+        assert!(c != run(b"other ctx", b"a", b"b", false));
+        assert!(c != run(b"ctx", b"b", b"a", false));
+    }
+
+    #[test]
+    #[should_panic]
+    fn empty_labels_are_rejected() {
+        let mut transcript = Transcript::new(b"TranscriptTest");
+        transcript.commit_scalar(b"", &Scalar::one());
+    }
 }
@@ -1,13 +1,27 @@
 #![deny(missing_docs)]
 #![allow(non_snake_case)]
 
+#[cfg(not(feature = "std"))]
+use alloc::string::String;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use core::borrow::Borrow;
+
+use clear_on_drop::clear::Clear;
+use curve25519_dalek::ristretto::RistrettoPoint;
 use curve25519_dalek::scalar::Scalar;
+#[cfg(feature = "parallel")]
+use curve25519_dalek::traits::Identity;
+use curve25519_dalek::traits::VartimeMultiscalarMul;
 use inner_product_proof::inner_product;
 
 /// Represents a degree-1 vector polynomial \\(\mathbf{a} + \mathbf{b} \cdot x\\).
+#[derive(Serialize, Deserialize)]
 pub struct VecPoly1(pub Vec<Scalar>, pub Vec<Scalar>);
 
 /// Represents a degree-2 scalar polynomial \\(a + b \cdot x + c \cdot x^2\\)
+#[derive(Serialize, Deserialize)]
 pub struct Poly2(pub Scalar, pub Scalar, pub Scalar);
 
 /// Provides an iterator over the powers of a `Scalar`.
@@ -40,10 +54,8 @@ pub fn exp_iter(x: Scalar) -> ScalarExp {
 
 pub fn add_vec(a: &[Scalar], b: &[Scalar]) -> Vec<Scalar> {
     let mut out = Vec::new();
-    if a.len() != b.len() {
-        // throw some error
-        println!("lengths of vectors don't match for vector addition");
-    }
+    // Callers always pass vectors of equal length; a mismatch would
+    // panic on the indexing below.
     for i in 0..a.len() {
         out.push(a[i] + b[i]);
     }
@@ -87,6 +99,25 @@ impl Poly2 {
     }
 }
 
+impl Drop for VecPoly1 {
+    fn drop(&mut self) {
+        for e in self.0.iter_mut() {
+            e.clear();
+        }
+        for e in self.1.iter_mut() {
+            e.clear();
+        }
+    }
+}
+
+impl Drop for Poly2 {
+    fn drop(&mut self) {
+        self.0.clear();
+        self.1.clear();
+        self.2.clear();
+    }
+}
+
 /// Raises `x` to the power `n` using binary exponentiation,
 /// with (1 to 2)*lg(n) scalar multiplications.
 /// TODO: a consttime version of this would be awfully similar to a Montgomery ladder.
@@ -138,6 +169,98 @@ pub fn read32(data: &[u8]) -> [u8; 32] {
     buf32
 }
 
+/// Encodes `bytes` as a lowercase hex string.
+pub fn to_hex(bytes: &[u8]) -> String {
+    const DIGITS: &[u8; 16] = b"0123456789abcdef";
+    let mut s = String::with_capacity(2 * bytes.len());
+    for &b in bytes {
+        s.push(DIGITS[(b >> 4) as usize] as char);
+        s.push(DIGITS[(b & 0xf) as usize] as char);
+    }
+    s
+}
+
+/// Decodes a hex string, accepting either case.  Returns `None` if
+/// `s` has odd length or contains a non-hex character.
+pub fn from_hex(s: &str) -> Option<Vec<u8>> {
+    fn digit(c: u8) -> Option<u8> {
+        match c {
+            b'0'...b'9' => Some(c - b'0'),
+            b'a'...b'f' => Some(c - b'a' + 10),
+            b'A'...b'F' => Some(c - b'A' + 10),
+            _ => None,
+        }
+    }
+
+    let s = s.as_bytes();
+    if s.len() % 2 != 0 {
+        return None;
+    }
+    s.chunks(2)
+        .map(|pair| Some(digit(pair[0])? << 4 | digit(pair[1])?))
+        .collect()
+}
+
+/// Converts a `u128` into a `Scalar`.
+pub fn scalar_from_u128(x: u128) -> Scalar {
+    let mut bytes = [0u8; 32];
+    for i in 0..16 {
+        bytes[i] = (x >> (8 * i)) as u8;
+    }
+    // x < 2^128 < l, so the bytes are a canonical encoding.
+    Scalar::from_bits(bytes)
+}
+
+/// Computes a variable-time multiscalar multiplication.
+///
+/// This is the same as `RistrettoPoint::vartime_multiscalar_mul`,
+/// but with the `parallel` feature the work is split across threads.
+#[cfg(not(feature = "parallel"))]
+pub fn vartime_multiscalar_mul<I, J>(scalars: I, points: J) -> RistrettoPoint
+where
+    I: IntoIterator,
+    I::Item: Borrow<Scalar>,
+    J: IntoIterator,
+    J::Item: Borrow<RistrettoPoint>,
+{
+    RistrettoPoint::vartime_multiscalar_mul(scalars, points)
+}
+
+/// Computes a variable-time multiscalar multiplication, splitting
+/// the terms into one chunk per thread of the `rayon` pool and adding
+/// the partial sums, which are computed in parallel.
+#[cfg(feature = "parallel")]
+pub fn vartime_multiscalar_mul<I, J>(scalars: I, points: J) -> RistrettoPoint
+where
+    I: IntoIterator,
+    I::Item: Borrow<Scalar>,
+    J: IntoIterator,
+    J::Item: Borrow<RistrettoPoint>,
+{
+    use rayon;
+    use rayon::prelude::*;
+
+    let scalars: Vec<Scalar> = scalars.into_iter().map(|s| *s.borrow()).collect();
+    let points: Vec<RistrettoPoint> = points.into_iter().map(|P| *P.borrow()).collect();
+    let len = scalars.len().min(points.len());
+    let threads = rayon::current_num_threads().max(1);
+    let chunk_size = ((len + threads - 1) / threads).max(1);
+
+    scalars[..len]
+        .par_chunks(chunk_size)
+        .zip(points[..len].par_chunks(chunk_size))
+        .map(|(s, P)| RistrettoPoint::vartime_multiscalar_mul(s, P))
+        .reduce(RistrettoPoint::identity, |acc, P| acc + P)
+}
+
+/// Returns a seeded RNG, so that tests which draw randomness from it
+/// are reproducible.
+#[cfg(test)]
+pub fn deterministic_rng(seed: [u8; 32]) -> ::rand::prng::ChaChaRng {
+    use rand::SeedableRng;
+    ::rand::prng::ChaChaRng::from_seed(seed)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -152,6 +275,51 @@ mod tests {
         assert_eq!(exp_2[3], Scalar::from(8u64));
     }
 
+    #[test]
+    fn scalar_from_u128_matches_u64() {
+        assert_eq!(scalar_from_u128(0), Scalar::zero());
+        assert_eq!(
+            scalar_from_u128(u64::max_value() as u128),
+            Scalar::from(u64::max_value())
+        );
+
+        let two_64 = Scalar::from(1u64 << 32) * Scalar::from(1u64 << 32);
+        assert_eq!(
+            scalar_from_u128(u128::max_value()),
+            Scalar::from(u64::max_value()) * two_64 + Scalar::from(u64::max_value())
+        );
+    }
+
+    #[test]
+    fn hex_roundtrip() {
+        let bytes = [0x00, 0x01, 0x7f, 0x80, 0xab, 0xff];
+        assert_eq!(to_hex(&bytes), "00017f80abff");
+        assert_eq!(from_hex("00017f80abff").unwrap(), bytes.to_vec());
+        assert_eq!(from_hex("00017F80ABFF").unwrap(), bytes.to_vec());
+        assert_eq!(from_hex("").unwrap(), Vec::<u8>::new());
+
+        assert_eq!(from_hex("abc"), None);
+        assert_eq!(from_hex("0g"), None);
+    }
+
+    #[test]
+    fn vartime_multiscalar_mul_matches_serial() {
+        use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
+
+        let mut rng = deterministic_rng([7u8; 32]);
+        // Lengths which don't divide evenly into chunks
+        for &len in [0, 1, 3, 17, 129].iter() {
+            let scalars: Vec<_> = (0..len).map(|_| Scalar::random(&mut rng)).collect();
+            let points: Vec<_> = (0..len)
+                .map(|_| RISTRETTO_BASEPOINT_POINT * Scalar::random(&mut rng))
+                .collect();
+            assert_eq!(
+                vartime_multiscalar_mul(&scalars, &points),
+                RistrettoPoint::vartime_multiscalar_mul(&scalars, &points)
+            );
+        }
+    }
+
     #[test]
     fn test_inner_product() {
         let a = vec![
@@ -220,4 +388,21 @@ mod tests {
         assert_eq!(sum_of_powers_slow(&x, 5), Scalar::from(11111u64));
         assert_eq!(sum_of_powers_slow(&x, 6), Scalar::from(111111u64));
     }
+
+    #[test]
+    fn poly2_is_cleared_on_drop() {
+        use core::mem::ManuallyDrop;
+        use core::ptr;
+
+        let mut p = ManuallyDrop::new(Poly2(
+            Scalar::from(1u64),
+            Scalar::from(2u64),
+            Scalar::from(3u64),
+        ));
+        unsafe { ptr::drop_in_place(&mut *p as *mut Poly2) };
+
+        assert_eq!(p.0, Scalar::zero());
+        assert_eq!(p.1, Scalar::zero());
+        assert_eq!(p.2, Scalar::zero());
+    }
 }
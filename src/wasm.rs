@@ -0,0 +1,109 @@
+//! WebAssembly bindings for single-party rangeproofs.
+//!
+//! These functions are exported with `wasm-bindgen` when the `wasm`
+//! feature is enabled.  Byte-valued arguments and results are passed
+//! as `Uint8Array`s on the JavaScript side: blinding factors as 32-byte
+//! canonical scalars, commitments as 32-byte compressed Ristretto
+//! points, and proofs in the encoding of [`RangeProof::to_bytes`].
+//!
+//! The bindings use the default [`PedersenGens`], fresh
+//! [`BulletproofGens`] for a single party, and a transcript with the
+//! label `"Bulletproofs wasm rangeproof"`, so that proofs created by
+//! `prove_range_single` verify with `verify_range_single`.
+
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::scalar::Scalar;
+use merlin::Transcript;
+use wasm_bindgen::prelude::*;
+
+use generators::{BulletproofGens, PedersenGens};
+use range_proof::{RangeProof, ALLOWED_BITSIZES};
+
+const TRANSCRIPT_LABEL: &'static [u8] = b"Bulletproofs wasm rangeproof";
+
+/// A rangeproof together with the commitment to the proven value.
+#[wasm_bindgen]
+pub struct SingleRangeProof {
+    proof: Vec<u8>,
+    commitment: Vec<u8>,
+}
+
+#[wasm_bindgen]
+impl SingleRangeProof {
+    /// The proof bytes.
+    pub fn proof(&self) -> Vec<u8> {
+        self.proof.clone()
+    }
+
+    /// The compressed commitment to the proven value.
+    pub fn commitment(&self) -> Vec<u8> {
+        self.commitment.clone()
+    }
+}
+
+fn read_32(bytes: &[u8], what: &str) -> Result<[u8; 32], JsValue> {
+    if bytes.len() != 32 {
+        return Err(JsValue::from_str(&format!("{} must be 32 bytes", what)));
+    }
+    let mut buf = [0u8; 32];
+    buf.copy_from_slice(bytes);
+    Ok(buf)
+}
+
+/// Rejects a bitsize before it is used to size the generators, so that
+/// JavaScript can't make us build an arbitrarily large table.
+fn check_bitsize(n: usize) -> Result<(), JsValue> {
+    if !ALLOWED_BITSIZES.contains(&n) {
+        return Err(JsValue::from_str(&format!(
+            "n must be one of {:?}",
+            ALLOWED_BITSIZES
+        )));
+    }
+    Ok(())
+}
+
+/// Proves that `value` lies in \\([0, 2^n)\\), committing to it with
+/// the given `blinding` factor.
+#[wasm_bindgen]
+pub fn prove_range_single(
+    value: u64,
+    blinding: &[u8],
+    n: usize,
+) -> Result<SingleRangeProof, JsValue> {
+    check_bitsize(n)?;
+    let blinding = Scalar::from_canonical_bytes(read_32(blinding, "blinding")?)
+        .ok_or_else(|| JsValue::from_str("blinding is not a canonical scalar"))?;
+
+    let pc_gens = PedersenGens::default();
+    let bp_gens = BulletproofGens::new(n, 1);
+    let mut transcript = Transcript::new(TRANSCRIPT_LABEL);
+
+    let (proof, commitment) =
+        RangeProof::prove_single(&bp_gens, &pc_gens, &mut transcript, value as u128, &blinding, n)
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+
+    Ok(SingleRangeProof {
+        proof: proof.to_bytes(),
+        commitment: commitment.as_bytes().to_vec(),
+    })
+}
+
+/// Verifies that `proof` shows the value committed to by
+/// `commitment` lies in \\([0, 2^n)\\).
+///
+/// Returns `false` if the proof is invalid, and an error if the
+/// inputs could not be parsed.
+#[wasm_bindgen]
+pub fn verify_range_single(proof: &[u8], commitment: &[u8], n: usize) -> Result<bool, JsValue> {
+    check_bitsize(n)?;
+    let commitment = CompressedRistretto(read_32(commitment, "commitment")?);
+    let proof = RangeProof::from_bytes(proof).map_err(|e| JsValue::from_str(&e.to_string()))?;
+
+    let pc_gens = PedersenGens::default();
+    let bp_gens = BulletproofGens::new(n, 1);
+    let mut transcript = Transcript::new(TRANSCRIPT_LABEL);
+
+    Ok(proof
+        .verify_single(&bp_gens, &pc_gens, &mut transcript, &commitment, n)
+        .is_ok())
+}
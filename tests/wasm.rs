@@ -0,0 +1,36 @@
+//! Browser round-trip test for the `wasm` bindings.
+//!
+//! Run with `make wasm-test`.
+#![cfg(all(target_arch = "wasm32", feature = "wasm"))]
+
+extern crate bulletproofs;
+extern crate wasm_bindgen_test;
+
+use bulletproofs::wasm::{prove_range_single, verify_range_single};
+use wasm_bindgen_test::*;
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+#[wasm_bindgen_test]
+fn range_proof_roundtrip() {
+    let mut blinding = [0u8; 32];
+    blinding[0] = 7;
+
+    let proved = prove_range_single(u64::max_value(), &blinding, 64).unwrap();
+    let proof = proved.proof();
+    let commitment = proved.commitment();
+
+    assert!(verify_range_single(&proof, &commitment, 64).unwrap());
+
+    let mut tampered = proof.clone();
+    tampered[100] ^= 1;
+    assert!(!verify_range_single(&tampered, &commitment, 64).unwrap_or(false));
+}
+
+#[wasm_bindgen_test]
+fn unsupported_bitsize_is_rejected() {
+    let blinding = [0u8; 32];
+
+    assert!(prove_range_single(0, &blinding, 1 << 30).is_err());
+    assert!(verify_range_single(&[], &[0u8; 32], 1 << 30).is_err());
+}
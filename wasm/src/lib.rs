@@ -0,0 +1,6 @@
+//! Builds the `wasm-bindgen` exports of [`bulletproofs::wasm`] into a
+//! `cdylib` for `wasm-pack`.
+
+extern crate bulletproofs;
+
+pub use bulletproofs::wasm::*;